@@ -0,0 +1,175 @@
+use bevy::{
+    ecs::{Entity, Resources, World},
+    scene::{
+        serde::SceneDeserializer,
+        test_support::{
+            build_sample_scene, build_sample_world, sample_type_registry, SamplePosition,
+        },
+        DynamicScene, PooledSceneSerializer, SceneSerializationCache,
+    },
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde::de::DeserializeSeed;
+
+const ENTITY_COUNTS: &[usize] = &[1_000, 10_000, 100_000];
+const SEED: u64 = 0;
+
+fn sample_scene(entity_count: usize) -> (DynamicScene, bevy::type_registry::TypeRegistry) {
+    (
+        build_sample_scene(SEED, entity_count),
+        sample_type_registry(),
+    )
+}
+
+fn deserialize(ron: &str, type_registry: &bevy::type_registry::TypeRegistry) -> DynamicScene {
+    let property_type_registry = type_registry.property.read();
+    let mut deserializer = ron::de::Deserializer::from_str(ron).unwrap();
+    SceneDeserializer {
+        property_type_registry: &property_type_registry,
+    }
+    .deserialize(&mut deserializer)
+    .unwrap()
+}
+
+fn bench_serialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scene_serialize");
+    group.sample_size(10);
+    for entity_count in ENTITY_COUNTS {
+        let (scene, type_registry) = sample_scene(*entity_count);
+        let property_type_registry = type_registry.property.read();
+        group.bench_with_input(
+            BenchmarkId::new("entities", entity_count),
+            entity_count,
+            |b, _| b.iter(|| black_box(scene.serialize_ron(&property_type_registry).unwrap())),
+        );
+    }
+    group.finish();
+}
+
+// serializes the same scene repeatedly, as a server sending a snapshot every frame would
+fn bench_pooled_serialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scene_repeated_serialize");
+    group.sample_size(10);
+    for entity_count in ENTITY_COUNTS {
+        let (scene, type_registry) = sample_scene(*entity_count);
+        let property_type_registry = type_registry.property.read();
+        group.bench_with_input(
+            BenchmarkId::new("fresh", entity_count),
+            entity_count,
+            |b, _| b.iter(|| black_box(scene.serialize_ron(&property_type_registry).unwrap())),
+        );
+        let mut serializer = PooledSceneSerializer::default();
+        group.bench_with_input(
+            BenchmarkId::new("pooled", entity_count),
+            entity_count,
+            |b, _| {
+                b.iter(|| {
+                    black_box(
+                        serializer
+                            .serialize_into(&scene, &property_type_registry)
+                            .unwrap()
+                            .len(),
+                    )
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_deserialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scene_deserialize");
+    group.sample_size(10);
+    for entity_count in ENTITY_COUNTS {
+        let (scene, type_registry) = sample_scene(*entity_count);
+        let ron = scene.serialize_ron(&type_registry.property.read()).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("entities", entity_count),
+            entity_count,
+            |b, _| b.iter(|| black_box(deserialize(&ron, &type_registry))),
+        );
+    }
+    group.finish();
+}
+
+fn bench_round_trip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scene_round_trip");
+    group.sample_size(10);
+    for entity_count in ENTITY_COUNTS {
+        let (world, _) = build_sample_world(SEED, *entity_count);
+        let type_registry = sample_type_registry();
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+        group.bench_with_input(
+            BenchmarkId::new("entities", entity_count),
+            entity_count,
+            |b, _| {
+                b.iter(|| {
+                    let scene = DynamicScene::from_world(&world, &type_registry.component.read());
+                    let ron = scene.serialize_ron(&type_registry.property.read()).unwrap();
+                    let mut loaded_world = World::default();
+                    deserialize(&ron, &type_registry)
+                        .write_to_world(&mut loaded_world, &resources)
+                        .unwrap();
+                    black_box(loaded_world)
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+// re-serializes a world in which 1% of the entities changed since the previous save
+fn bench_cached_reserialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scene_reserialize");
+    group.sample_size(10);
+    for entity_count in ENTITY_COUNTS {
+        let (mut world, component_registry) = build_sample_world(SEED, *entity_count);
+        let type_registry = sample_type_registry();
+        let property_type_registry = type_registry.property.read();
+        let mut cache = SceneSerializationCache::default();
+        cache
+            .serialize_ron(&world, &component_registry, &property_type_registry)
+            .unwrap();
+        world.clear_trackers();
+        let changed = world.query::<Entity>().step_by(100).collect::<Vec<_>>();
+        for entity in changed {
+            world.get_mut::<SamplePosition>(entity).unwrap().x += 1.0;
+        }
+
+        group.bench_with_input(
+            BenchmarkId::new("full", entity_count),
+            entity_count,
+            |b, _| {
+                b.iter(|| {
+                    let scene = DynamicScene::from_world(&world, &component_registry);
+                    black_box(scene.serialize_ron(&property_type_registry).unwrap())
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("cached", entity_count),
+            entity_count,
+            |b, _| {
+                b.iter(|| {
+                    black_box(
+                        cache
+                            .serialize_ron(&world, &component_registry, &property_type_registry)
+                            .unwrap(),
+                    )
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_serialize,
+    bench_pooled_serialize,
+    bench_deserialize,
+    bench_round_trip,
+    bench_cached_reserialize
+);
+criterion_main!(benches);
@@ -9,6 +9,7 @@ use std::{
     collections::{BTreeMap, HashMap, HashSet},
     hash::{BuildHasher, Hash},
     ops::Range,
+    time::Duration,
 };
 
 impl<T> Properties for Vec<T>
@@ -103,6 +104,104 @@ where
     }
 }
 
+// Components can't hold borrowed slices (`&[T]`), so store them as `Vec<T>` or `Box<[T]>`.
+impl<T> Properties for Box<[T]>
+where
+    T: Property + Clone + Default,
+{
+    fn prop(&self, _name: &str) -> Option<&dyn Property> {
+        None
+    }
+
+    fn prop_mut(&mut self, _name: &str) -> Option<&mut dyn Property> {
+        None
+    }
+
+    fn prop_with_index(&self, index: usize) -> Option<&dyn Property> {
+        Some(&self[index])
+    }
+
+    fn prop_with_index_mut(&mut self, index: usize) -> Option<&mut dyn Property> {
+        Some(&mut self[index])
+    }
+
+    fn prop_name(&self, _index: usize) -> Option<&str> {
+        None
+    }
+
+    fn prop_len(&self) -> usize {
+        self.len()
+    }
+
+    fn iter_props(&self) -> PropertyIter {
+        PropertyIter::new(self)
+    }
+}
+
+impl<T> Property for Box<[T]>
+where
+    T: Property + Clone + Default,
+{
+    fn type_name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    fn any(&self) -> &dyn Any {
+        self
+    }
+
+    fn any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn clone_prop(&self) -> Box<dyn Property> {
+        Box::new(self.clone())
+    }
+
+    fn set(&mut self, value: &dyn Property) {
+        if let Some(properties) = value.as_properties() {
+            let len = properties.prop_len();
+            if len != self.len() {
+                // boxed slices can't grow in place, so resize through a Vec
+                let mut vec = std::mem::take(self).into_vec();
+                vec.resize_with(len, T::default);
+                *self = vec.into_boxed_slice();
+            }
+
+            if properties.property_type() != self.property_type() {
+                panic!(
+                    "Properties type mismatch. This type is {:?} but the applied type is {:?}",
+                    self.property_type(),
+                    properties.property_type()
+                );
+            }
+            for (i, prop) in properties.iter_props().enumerate() {
+                if let Some(p) = self.prop_with_index_mut(i) {
+                    p.apply(prop)
+                }
+            }
+        } else {
+            panic!("attempted to apply non-Properties type to Properties type");
+        }
+    }
+
+    fn apply(&mut self, value: &dyn Property) {
+        self.set(value);
+    }
+
+    fn as_properties(&self) -> Option<&dyn Properties> {
+        Some(self)
+    }
+
+    fn serializable<'a>(&'a self, registry: &'a PropertyTypeRegistry) -> Serializable<'a> {
+        Serializable::Owned(Box::new(SeqSerializer::new(self, registry)))
+    }
+
+    fn property_type(&self) -> PropertyType {
+        PropertyType::Seq
+    }
+}
+
 // impl_property!(SEQUENCE, VecDeque<T> where T: Clone + Send + Sync + Serialize + 'static);
 impl_property!(Option<T> where T: Clone + Send + Sync + Serialize + for<'de> Deserialize<'de> + 'static);
 impl_property!(HashSet<T, H> where T: Clone + Eq + Send + Sync + Hash + Serialize + for<'de> Deserialize<'de> + 'static, H: Clone + Send + Sync + Default + BuildHasher + 'static);
@@ -114,6 +213,7 @@ impl_property!(BTreeMap<K, V> where
     K: Clone + Ord + Send + Sync + Serialize + for<'de> Deserialize<'de> + 'static,
     V: Clone + Send + Sync + Serialize + for<'de> Deserialize<'de> + 'static);
 impl_property!(Range<T> where T: Clone + Send + Sync + Serialize + for<'de> Deserialize<'de> + 'static);
+impl_property!(Duration);
 
 // TODO: Implement lossless primitive types in RON and remove all of these primitive "cast checks"
 impl Property for String {
@@ -6,11 +6,13 @@ mod dynamic_properties;
 mod properties;
 mod property;
 mod type_registry;
+mod variant_migration;
 
 pub use dynamic_properties::*;
 pub use properties::*;
 pub use property::*;
 pub use type_registry::*;
+pub use variant_migration::*;
 
 pub use bevy_property_derive::*;
 pub use erased_serde;
@@ -1,5 +1,8 @@
-use crate::{DeserializeProperty, Property};
+use crate::{
+    variant_migration::deserialize_migrated, DeserializeProperty, MigrateVariants, Property,
+};
 use bevy_utils::{HashMap, HashSet};
+use serde::de::DeserializeOwned;
 use std::{any::TypeId, fmt};
 
 #[derive(Debug, Default)]
@@ -18,6 +21,17 @@ impl PropertyTypeRegistry {
         self.add_registration(registration);
     }
 
+    /// Registers the enum `T`, running its [MigrateVariants] impl on every saved variant before
+    /// it is deserialized.
+    pub fn register_with_variant_migration<T>(&mut self)
+    where
+        T: Property + DeserializeProperty + DeserializeOwned + MigrateVariants,
+    {
+        let mut registration = PropertyTypeRegistration::of::<T>();
+        registration.deserialize_fn = deserialize_migrated::<T>;
+        self.add_registration(registration);
+    }
+
     fn add_registration(&mut self, registration: PropertyTypeRegistration) {
         let short_name = registration.short_name.to_string();
         if self.short_names.contains_key(&short_name) || self.ambigous_names.contains(&short_name) {
@@ -0,0 +1,352 @@
+use crate::{Property, PropertyTypeRegistry};
+use erased_serde::Deserializer;
+use serde::{
+    de::{
+        self, DeserializeOwned, DeserializeSeed, EnumAccess, IgnoredAny, IntoDeserializer,
+        VariantAccess, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+use std::{convert::TryFrom, fmt, marker::PhantomData};
+
+/// What to do with a saved enum variant before it is deserialized.
+pub enum VariantMigration<T> {
+    /// Deserializes the saved payload as the variant with this name.
+    Rename(&'static str),
+    /// Builds the value from the saved payload, which is read as a newtype variant.
+    Transform(fn(&mut dyn Deserializer) -> Result<T, erased_serde::Error>),
+    /// Replaces a saved unit variant that no longer exists.
+    Fallback(T),
+    /// Replaces a saved newtype or tuple variant with this many fields that no longer exists,
+    /// ignoring its payload.
+    FallbackTuple(usize, T),
+    /// Replaces a saved struct variant with these fields that no longer exists, ignoring its
+    /// payload.
+    FallbackStruct(&'static [&'static str], T),
+}
+
+/// Remaps enum variants written by older versions of a type. Register types implementing this
+/// with [PropertyTypeRegistry::register_with_variant_migration].
+pub trait MigrateVariants: Sized {
+    /// Returns the migration for a saved variant name, or `None` to deserialize it unchanged.
+    fn migrate_variant(name: &str) -> Option<VariantMigration<Self>>;
+
+    /// The variant names in the order older versions declared them. Formats that save variants
+    /// by index instead of by name, like bincode, look the saved name up here.
+    const SAVED_VARIANTS: &'static [&'static str] = &[];
+}
+
+pub(crate) fn deserialize_migrated<T>(
+    deserializer: &mut dyn Deserializer,
+    _registry: &PropertyTypeRegistry,
+) -> Result<Box<dyn Property>, erased_serde::Error>
+where
+    T: Property + DeserializeOwned + MigrateVariants,
+{
+    let value = de::Deserializer::deserialize_enum(
+        deserializer,
+        std::any::type_name::<T>(),
+        &[],
+        MigratingVisitor::<T>(PhantomData),
+    )?;
+    Ok(Box::new(value))
+}
+
+struct MigratingVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for MigratingVisitor<T>
+where
+    T: DeserializeOwned + MigrateVariants,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("enum variant")
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (saved, variant) = data.variant_seed(VariantNameSeed(T::SAVED_VARIANTS))?;
+        let name = match saved {
+            SavedVariant::Name(name) => name,
+            // an index the saved variant list doesn't cover was written by the current version
+            SavedVariant::Index(index) => {
+                return T::deserialize(MigratedVariant {
+                    saved: SavedVariant::Index(index),
+                    variant,
+                })
+            }
+        };
+        match T::migrate_variant(&name) {
+            None => T::deserialize(MigratedVariant {
+                saved: SavedVariant::Name(name),
+                variant,
+            }),
+            Some(VariantMigration::Rename(name)) => T::deserialize(MigratedVariant {
+                saved: SavedVariant::Name(name.to_string()),
+                variant,
+            }),
+            Some(VariantMigration::Transform(transform)) => {
+                variant.newtype_variant_seed(TransformSeed(transform))
+            }
+            Some(VariantMigration::Fallback(value)) => {
+                variant.unit_variant()?;
+                Ok(value)
+            }
+            Some(VariantMigration::FallbackTuple(len, value)) => {
+                variant.tuple_variant(len, IgnoredAny)?;
+                Ok(value)
+            }
+            Some(VariantMigration::FallbackStruct(fields, value)) => {
+                variant.struct_variant(fields, IgnoredAny)?;
+                Ok(value)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum SavedVariant {
+    Name(String),
+    Index(u32),
+}
+
+/// Reads a saved variant name, or looks it up in the saved variant list for formats that save
+/// variant indices. Indices past the end of the list are kept as they are.
+struct VariantNameSeed(&'static [&'static str]);
+
+impl<'de> DeserializeSeed<'de> for VariantNameSeed {
+    type Value = SavedVariant;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de> Visitor<'de> for VariantNameSeed {
+    type Value = SavedVariant;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("variant name or index")
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match self.0.get(v as usize) {
+            Some(name) => Ok(SavedVariant::Name(name.to_string())),
+            None => u32::try_from(v)
+                .map(SavedVariant::Index)
+                .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(SavedVariant::Name(v.to_string()))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        std::str::from_utf8(v)
+            .map(|name| SavedVariant::Name(name.to_string()))
+            .map_err(|_| E::invalid_value(de::Unexpected::Bytes(v), &self))
+    }
+}
+
+/// Replays a saved variant under a (possibly new) name or its index, so `T`'s own
+/// [Deserialize](serde::Deserialize) impl reads the payload.
+struct MigratedVariant<A> {
+    saved: SavedVariant,
+    variant: A,
+}
+
+impl<'de, A> de::Deserializer<'de> for MigratedVariant<A>
+where
+    A: VariantAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, A> EnumAccess<'de> for MigratedVariant<A>
+where
+    A: VariantAccess<'de>,
+{
+    type Error = A::Error;
+    type Variant = A;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = match self.saved {
+            SavedVariant::Name(name) => seed.deserialize(name.into_deserializer())?,
+            SavedVariant::Index(index) => seed.deserialize(index.into_deserializer())?,
+        };
+        Ok((value, self.variant))
+    }
+}
+
+struct TransformSeed<T>(fn(&mut dyn Deserializer) -> Result<T, erased_serde::Error>);
+
+impl<'de, T> DeserializeSeed<'de> for TransformSeed<T> {
+    type Value = T;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let mut erased = Deserializer::erase(deserializer);
+        (self.0)(&mut erased).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MigrateVariants, SavedVariant, VariantMigration, VariantNameSeed};
+    use crate::{impl_property, Property, PropertyTypeRegistry};
+    use bincode::Options;
+    use serde::{
+        de::{value, DeserializeSeed, IntoDeserializer},
+        Deserialize, Serialize,
+    };
+
+    // version 1 had `Crawl`, `Run(f32)`, `Jump(f32)`, `Slide(f32, f32)` and `Swim { depth: f32 }`
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    enum Movement {
+        Idle,
+        Sprint(f32),
+        Climb { height: f32 },
+    }
+
+    impl_property!(Movement);
+
+    impl MigrateVariants for Movement {
+        fn migrate_variant(name: &str) -> Option<VariantMigration<Self>> {
+            match name {
+                "Run" => Some(VariantMigration::Rename("Sprint")),
+                "Jump" => Some(VariantMigration::Transform(|deserializer| {
+                    let height = f32::deserialize(deserializer)?;
+                    Ok(Movement::Climb { height })
+                })),
+                "Crawl" => Some(VariantMigration::Fallback(Movement::Idle)),
+                "Slide" => Some(VariantMigration::FallbackTuple(2, Movement::Idle)),
+                "Swim" => Some(VariantMigration::FallbackStruct(&["depth"], Movement::Idle)),
+                _ => None,
+            }
+        }
+
+        const SAVED_VARIANTS: &'static [&'static str] = &["Crawl", "Run", "Jump", "Slide", "Swim"];
+    }
+
+    fn load(registry: &PropertyTypeRegistry, ron: &str) -> Movement {
+        let mut deserializer = ron::de::Deserializer::from_str(ron).unwrap();
+        let property = registry
+            .get("Movement")
+            .unwrap()
+            .deserialize(&mut deserializer, registry)
+            .unwrap();
+        property.any().downcast_ref::<Movement>().unwrap().clone()
+    }
+
+    #[test]
+    fn migrate_variants() {
+        let mut registry = PropertyTypeRegistry::default();
+        registry.register_with_variant_migration::<Movement>();
+
+        assert_eq!(load(&registry, "Run(2.5)"), Movement::Sprint(2.5));
+        assert_eq!(
+            load(&registry, "Jump(1.5)"),
+            Movement::Climb { height: 1.5 }
+        );
+        assert_eq!(load(&registry, "Crawl"), Movement::Idle);
+        assert_eq!(load(&registry, "Slide(1.0, 2.0)"), Movement::Idle);
+        assert_eq!(load(&registry, "Swim(depth: 3.0)"), Movement::Idle);
+
+        // current variants are deserialized unchanged
+        assert_eq!(load(&registry, "Sprint(3.0)"), Movement::Sprint(3.0));
+        assert_eq!(
+            load(&registry, "Climb(height: 2.0)"),
+            Movement::Climb { height: 2.0 }
+        );
+        assert_eq!(load(&registry, "Idle"), Movement::Idle);
+    }
+
+    #[test]
+    fn variant_index() {
+        let seed = || VariantNameSeed(Movement::SAVED_VARIANTS);
+        let name: Result<_, value::Error> = seed().deserialize(2u32.into_deserializer());
+        assert_eq!(name.unwrap(), SavedVariant::Name("Jump".to_string()));
+        let name: Result<_, value::Error> = seed().deserialize(5u32.into_deserializer());
+        assert_eq!(name.unwrap(), SavedVariant::Index(5));
+    }
+
+    // version 1 only had `Sun`, which became `Clear`. `Rain` was added later
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    enum Weather {
+        Clear,
+        Rain(f32),
+    }
+
+    impl_property!(Weather);
+
+    impl MigrateVariants for Weather {
+        fn migrate_variant(name: &str) -> Option<VariantMigration<Self>> {
+            match name {
+                "Sun" => Some(VariantMigration::Rename("Clear")),
+                _ => None,
+            }
+        }
+
+        const SAVED_VARIANTS: &'static [&'static str] = &["Sun"];
+    }
+
+    #[derive(Serialize)]
+    enum WeatherV1 {
+        Sun,
+    }
+
+    #[test]
+    fn migrate_variant_indices() {
+        let mut registry = PropertyTypeRegistry::default();
+        registry.register_with_variant_migration::<Weather>();
+        let options = bincode::DefaultOptions::new();
+        let load = |bytes: Vec<u8>| {
+            let mut deserializer = bincode::Deserializer::from_slice(&bytes, options);
+            let property = registry
+                .get("Weather")
+                .unwrap()
+                .deserialize(&mut deserializer, &registry)
+                .unwrap();
+            property.any().downcast_ref::<Weather>().unwrap().clone()
+        };
+
+        let old = options.serialize(&WeatherV1::Sun).unwrap();
+        assert_eq!(load(old), Weather::Clear);
+        let current = options.serialize(&Weather::Rain(0.5)).unwrap();
+        assert_eq!(load(current), Weather::Rain(0.5));
+    }
+}
@@ -0,0 +1,120 @@
+use bevy_ecs::{Component, World};
+use bevy_property::{
+    property_serde::{MapValueSerializer, SeqValueSerializer},
+    DynamicProperties, PropertyType, PropertyTypeRegistry,
+};
+use bevy_type_registry::ComponentRegistry;
+use serde::{ser::SerializeSeq, Serialize};
+use std::any::TypeId;
+
+/// Collects every `T` component in `world` into a single list, regardless of the entity or
+/// archetype it belongs to. Returns `None` if `T` isn't registered.
+pub fn component_column<T: Component>(
+    world: &World,
+    component_registry: &ComponentRegistry,
+) -> Option<Vec<DynamicProperties>> {
+    let registration = component_registry.get(&TypeId::of::<T>())?;
+    let mut column = Vec::new();
+    for archetype in world.archetypes().filter(|archetype| archetype.has::<T>()) {
+        for index in 0..archetype.len() {
+            match registration.get_serialized_component(archetype, index) {
+                Ok(properties) => column.push(properties),
+                Err(error) => log::warn!(
+                    "Skipping a {} component: {}",
+                    registration.short_name,
+                    error
+                ),
+            }
+        }
+    }
+
+    Some(column)
+}
+
+/// Serializes a component column as a list of plain field maps (or lists), without the type
+/// annotations used in scene files. Meant for exporting to data analysis tools.
+pub struct ComponentColumnSerializer<'a> {
+    pub column: &'a [DynamicProperties],
+    pub registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a> Serialize for ComponentColumnSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_seq(Some(self.column.len()))?;
+        for properties in self.column.iter() {
+            match properties.property_type {
+                PropertyType::Map => state.serialize_element(&MapValueSerializer {
+                    properties,
+                    registry: self.registry,
+                })?,
+                PropertyType::Seq => state.serialize_element(&SeqValueSerializer {
+                    properties,
+                    registry: self.registry,
+                })?,
+                PropertyType::Value => {
+                    return Err(serde::ser::Error::custom(
+                        "DynamicProperties cannot be Value type",
+                    ))
+                }
+            }
+        }
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{component_column, ComponentColumnSerializer};
+    use crate::test_support::{register_sample_components, SamplePosition, SampleVelocity};
+    use bevy_ecs::World;
+    use bevy_property::PropertyTypeRegistry;
+    use bevy_type_registry::ComponentRegistry;
+
+    #[test]
+    fn export_position_column() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        world.spawn((
+            SamplePosition {
+                x: 3.0,
+                y: 4.0,
+                z: 0.0,
+            },
+            SampleVelocity {
+                x: 1.0,
+                ..Default::default()
+            },
+        ));
+        world.spawn((SampleVelocity {
+            y: 1.0,
+            ..Default::default()
+        },));
+
+        let column = component_column::<SamplePosition>(&world, &component_registry).unwrap();
+        assert_eq!(column.len(), 2);
+        let json = serde_json::to_value(ComponentColumnSerializer {
+            column: &column,
+            registry: &PropertyTypeRegistry::default(),
+        })
+        .unwrap();
+        let mut rows = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|row| (row["x"].as_f64().unwrap(), row["y"].as_f64().unwrap()))
+            .collect::<Vec<_>>();
+        rows.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(rows, vec![(1.0, 2.0), (3.0, 4.0)]);
+
+        assert!(component_column::<String>(&world, &component_registry).is_none());
+    }
+}
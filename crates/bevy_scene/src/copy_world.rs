@@ -0,0 +1,98 @@
+use bevy_ecs::{EntityMap, MapEntitiesError, Resources, World};
+use bevy_type_registry::ComponentRegistry;
+
+/// Copies every registered component of `source` into new entities in `destination`, without
+/// going through a [Scene](crate::Scene). Returns the mapping from source to destination entities,
+/// or the first reference to an entity that `source` doesn't contain.
+pub fn copy_world(
+    source: &World,
+    destination: &mut World,
+    component_registry: &ComponentRegistry,
+    resources: &Resources,
+) -> Result<EntityMap, MapEntitiesError> {
+    let mut entity_map = EntityMap::default();
+    for archetype in source.archetypes() {
+        for source_entity in archetype.iter_entities() {
+            let entity = *entity_map
+                .entry(*source_entity)
+                .or_insert_with(|| destination.reserve_entity());
+            for type_info in archetype.types() {
+                if let Some(component_registration) = component_registry.get(&type_info.id()) {
+                    component_registration.component_copy(
+                        source,
+                        destination,
+                        resources,
+                        *source_entity,
+                        entity,
+                    );
+                }
+            }
+        }
+    }
+
+    for component_registration in component_registry.iter() {
+        component_registration.map_entities(destination, &entity_map)?;
+    }
+
+    Ok(entity_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::copy_world;
+    use crate::test_support::{register_sample_parent, SampleParent};
+    use bevy_ecs::{Entity, MapEntitiesError, Resources, World};
+    use bevy_property::Properties;
+    use bevy_type_registry::ComponentRegistry;
+
+    #[derive(Properties, Default)]
+    struct Name {
+        value: String,
+    }
+
+    #[test]
+    fn copy_two_entities() {
+        let mut component_registry = ComponentRegistry::default();
+        component_registry.register::<Name>();
+        let resources = Resources::default();
+
+        let mut source = World::default();
+        let a = source.spawn((Name {
+            value: "a".to_string(),
+        },));
+        let b = source.spawn((Name {
+            value: "b".to_string(),
+        },));
+        let mut destination = World::default();
+        destination.spawn((Name {
+            value: "existing".to_string(),
+        },));
+
+        let entity_map =
+            copy_world(&source, &mut destination, &component_registry, &resources).unwrap();
+        assert_eq!(entity_map.keys().count(), 2);
+        let copied_a = entity_map.get(a).unwrap();
+        let copied_b = entity_map.get(b).unwrap();
+        assert_ne!(copied_a, copied_b);
+        assert_eq!(destination.get::<Name>(copied_a).unwrap().value, "a");
+        assert_eq!(destination.get::<Name>(copied_b).unwrap().value, "b");
+        assert_eq!(destination.query::<&Name>().count(), 3);
+    }
+
+    #[test]
+    fn copy_dangling_reference() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_parent(&mut component_registry);
+        let resources = Resources::default();
+
+        let mut source = World::default();
+        let outside = Entity::new(42);
+        source.spawn((SampleParent(outside),));
+        let mut destination = World::default();
+
+        let result = copy_world(&source, &mut destination, &component_registry, &resources);
+        assert!(
+            matches!(result, Err(MapEntitiesError::EntityNotFound(entity)) if entity == outside)
+        );
+    }
+}
@@ -1,21 +1,68 @@
-use crate::{serde::SceneSerializer, Scene};
+use crate::{
+    serde::{ComponentsSerializer, SceneSerializer, ENTITY_FIELD_COMPONENTS},
+    Scene, SceneEntityAllocator, SceneMetadata,
+};
 use anyhow::Result;
-use bevy_ecs::{EntityMap, Resources, World};
-use bevy_property::{DynamicProperties, PropertyTypeRegistry};
-use bevy_type_registry::{ComponentRegistry, TypeRegistry, TypeUuid};
+use bevy_ecs::{
+    Archetype, Component, Entity as WorldEntity, EntityMap, MapEntitiesError, Resources, World,
+};
+use bevy_property::{DynamicProperties, Properties, PropertyTypeRegistry};
+use bevy_type_registry::{ComponentRegistry, ResourceRegistry, TypeRegistry, TypeUuid};
+use bevy_utils::{stable_hash, HashMap, HashSet};
 use serde::Serialize;
+use std::io::Write;
 use thiserror::Error;
 
+/// The position of an entity in the order entities were spawned. Unlike entity ids, which are
+/// recycled after a despawn, this keeps increasing. Register it as a component so it is saved
+/// with the scene.
+#[derive(Properties, Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SpawnSequence {
+    pub index: u64,
+}
+
 #[derive(Error, Debug)]
 pub enum DynamicSceneToWorldError {
     #[error("Scene contains an unregistered component.")]
     UnregisteredComponent { type_name: String },
+    #[error("Scene contains a component that can't be loaded: {reason}")]
+    InvalidComponent { type_name: String, reason: String },
+    #[error("Scene contains an unregistered resource.")]
+    UnregisteredResource { type_name: String },
+    #[error("Scene contains a resource whose type name is ambiguous: {type_name}")]
+    AmbiguousResource { type_name: String },
+    #[error("Scene entity {entity} was rejected: {reason}")]
+    Rejected { entity: u32, reason: String },
+    #[error("Scene contains an entity reference that could not be mapped.")]
+    MapEntities(#[from] MapEntitiesError),
+}
+
+/// Determines how [DynamicScene::write_to_world_with_policy] reacts to a component it cannot
+/// write, such as one whose type is no longer registered.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ComponentErrorPolicy {
+    /// Stop writing the scene and return the error.
+    Fail,
+    /// Log the error and keep the entity's remaining components.
+    SkipComponent,
+}
+
+impl Default for ComponentErrorPolicy {
+    fn default() -> Self {
+        ComponentErrorPolicy::Fail
+    }
 }
 
+/// A format-agnostic snapshot of a scene's registered components. Extracting it walks the world
+/// once, after which it can be serialized with [SceneSerializer] into any number of serde formats.
 #[derive(Default, TypeUuid)]
 #[uuid = "749479b1-fb8c-4ff8-a775-623aa76014f5"]
 pub struct DynamicScene {
     pub entities: Vec<Entity>,
+    pub resources: Vec<DynamicProperties>,
+    /// Advisory information about the world the scene was taken from. Loaders are free to ignore
+    /// it.
+    pub metadata: Option<SceneMetadata>,
 }
 
 pub struct Entity {
@@ -23,14 +70,55 @@ pub struct Entity {
     pub components: Vec<DynamicProperties>,
 }
 
+impl Entity {
+    /// Hashes the serialized form of this entity's components with [stable_hash]. The hash only
+    /// changes when the components do, so comparing it across saves finds the entities that
+    /// changed.
+    pub fn component_hash(&self, registry: &PropertyTypeRegistry) -> Result<u64, ron::Error> {
+        let ron = ron::ser::to_string(&ComponentsSerializer {
+            components: &self.components,
+            registry,
+        })?;
+        Ok(stable_hash(ron.as_bytes()))
+    }
+}
+
 impl DynamicScene {
     pub fn from_scene(scene: &Scene, component_registry: &ComponentRegistry) -> Self {
         Self::from_world(&scene.world, component_registry)
     }
 
+    /// Only reads `world`: components are copied out through shared references, so change
+    /// tracking is untouched and several threads can extract scenes from the same world at once.
     pub fn from_world(world: &World, component_registry: &ComponentRegistry) -> Self {
+        Self::from_world_filtered(world, component_registry, |_| true)
+    }
+
+    /// Like [DynamicScene::from_world], but leaves out every entity that has a `T` component,
+    /// e.g. a `Transient` marker on entities that should never be saved. `T` doesn't need to be
+    /// registered.
+    pub fn from_world_excluding<T: Component>(
+        world: &World,
+        component_registry: &ComponentRegistry,
+    ) -> Self {
+        Self::from_world_filtered(world, component_registry, |archetype| !archetype.has::<T>())
+    }
+
+    /// Components registered with `retain_entities` drop their references to the entities that
+    /// `filter` leaves out.
+    fn from_world_filtered(
+        world: &World,
+        component_registry: &ComponentRegistry,
+        filter: impl Fn(&Archetype) -> bool,
+    ) -> Self {
+        let excluded = world
+            .archetypes()
+            .filter(|archetype| !filter(archetype))
+            .flat_map(|archetype| archetype.iter_entities().cloned())
+            .collect::<HashSet<_>>();
+        let keep = |entity: WorldEntity| !excluded.contains(&entity);
         let mut scene = DynamicScene::default();
-        for archetype in world.archetypes() {
+        for archetype in world.archetypes().filter(|archetype| filter(archetype)) {
             let mut entities = Vec::new();
             for (index, entity) in archetype.iter_entities().enumerate() {
                 if index == entities.len() {
@@ -41,10 +129,22 @@ impl DynamicScene {
                 }
                 for type_info in archetype.types() {
                     if let Some(component_registration) = component_registry.get(&type_info.id()) {
-                        let properties =
-                            component_registration.get_component_properties(&archetype, index);
+                        let properties = if excluded.is_empty() {
+                            component_registration.get_serialized_component(&archetype, index)
+                        } else {
+                            component_registration
+                                .get_serialized_component_retaining(&archetype, index, &keep)
+                        };
 
-                        entities[index].components.push(properties.to_dynamic());
+                        match properties {
+                            Ok(properties) => entities[index].components.push(properties),
+                            Err(error) => log::warn!(
+                                "Skipping {} of entity {}: {}",
+                                component_registration.short_name,
+                                entity.id(),
+                                error
+                            ),
+                        }
                     }
                 }
             }
@@ -55,46 +155,232 @@ impl DynamicScene {
         scene
     }
 
+    /// Like [DynamicScene::from_world], but orders the entities by their [SpawnSequence] instead
+    /// of by archetype, so writing the scene to a world spawns them again in the order they were
+    /// first spawned. Entities without a [SpawnSequence] follow, ordered by id.
+    pub fn from_world_in_spawn_order(
+        world: &World,
+        component_registry: &ComponentRegistry,
+    ) -> Self {
+        let sequences = world
+            .query::<(WorldEntity, &SpawnSequence)>()
+            .map(|(entity, sequence)| (entity.id(), sequence.index))
+            .collect::<HashMap<_, _>>();
+        let mut scene = Self::from_world(world, component_registry);
+        scene
+            .entities
+            .sort_by_key(|entity| match sequences.get(&entity.entity) {
+                Some(index) => (false, *index),
+                None => (true, entity.entity as u64),
+            });
+        scene
+    }
+
+    /// Stores a copy of every registered resource that currently exists in `resources`.
+    pub fn add_resources(&mut self, resources: &Resources, resource_registry: &ResourceRegistry) {
+        for resource_registration in resource_registry.iter() {
+            if let Some(properties) = resource_registration.get_resource_properties(resources) {
+                self.resources.push(properties);
+            }
+        }
+    }
+
+    pub fn write_resources(
+        &self,
+        resources: &mut Resources,
+    ) -> Result<(), DynamicSceneToWorldError> {
+        let type_registry = resources.get::<TypeRegistry>().unwrap().clone();
+        let resource_registry = type_registry.resource.read();
+        for resource in self.resources.iter() {
+            let type_name = resource.type_name.to_string();
+            let resource_registration = match resource_registry.get_with_name(&type_name) {
+                Some(registration) => registration,
+                None if resource_registry.is_ambiguous(&type_name) => {
+                    return Err(DynamicSceneToWorldError::AmbiguousResource { type_name })
+                }
+                None => return Err(DynamicSceneToWorldError::UnregisteredResource { type_name }),
+            };
+            resource_registration.insert_property_into_resources(resources, resource);
+        }
+
+        Ok(())
+    }
+
     pub fn write_to_world(
         &self,
         world: &mut World,
         resources: &Resources,
+    ) -> Result<(), DynamicSceneToWorldError> {
+        self.write_to_world_with_policy(world, resources, ComponentErrorPolicy::Fail)
+    }
+
+    pub fn write_to_world_with_policy(
+        &self,
+        world: &mut World,
+        resources: &Resources,
+        policy: ComponentErrorPolicy,
+    ) -> Result<(), DynamicSceneToWorldError> {
+        self.write_to_world_internal(world, resources, &mut |entity, error| match policy {
+            ComponentErrorPolicy::Fail => Err(error),
+            ComponentErrorPolicy::SkipComponent => {
+                match entity {
+                    Some(entity) => {
+                        log::warn!("Skipping a component of scene entity {}: {}", entity, error)
+                    }
+                    None => log::warn!("Skipping entity references of the scene: {}", error),
+                }
+                Ok(())
+            }
+        })
+    }
+
+    /// Writes every component that can be written and returns the errors for the ones that
+    /// can't, paired with the id of the scene entity they belong to. Entity references that
+    /// can't be mapped aren't tied to one scene entity and are paired with `None`. This reports
+    /// everything that is wrong with a scene in one pass.
+    pub fn write_to_world_collecting_errors(
+        &self,
+        world: &mut World,
+        resources: &Resources,
+    ) -> Vec<(Option<u32>, DynamicSceneToWorldError)> {
+        let mut errors = Vec::new();
+        let result = self.write_to_world_internal(world, resources, &mut |entity, error| {
+            errors.push((entity, error));
+            Ok(())
+        });
+        errors.extend(result.err().map(|error| (None, error)));
+        errors
+    }
+
+    fn write_to_world_internal(
+        &self,
+        world: &mut World,
+        resources: &Resources,
+        on_error: &mut dyn FnMut(
+            Option<u32>,
+            DynamicSceneToWorldError,
+        ) -> Result<(), DynamicSceneToWorldError>,
     ) -> Result<(), DynamicSceneToWorldError> {
         let type_registry = resources.get::<TypeRegistry>().unwrap();
         let component_registry = type_registry.component.read();
         let mut entity_map = EntityMap::default();
+        let mut allocator = SceneEntityAllocator::default();
         for scene_entity in self.entities.iter() {
             let new_entity = world.reserve_entity();
-            entity_map.insert(bevy_ecs::Entity::new(scene_entity.entity), new_entity);
+            entity_map.insert(allocator.register(scene_entity.entity), new_entity);
             for component in scene_entity.components.iter() {
-                let component_registration = component_registry
-                    .get_with_name(&component.type_name)
-                    .ok_or_else(|| DynamicSceneToWorldError::UnregisteredComponent {
-                        type_name: component.type_name.to_string(),
-                    })?;
+                let component_registration =
+                    match component_registry.get_with_name(&component.type_name) {
+                        Some(component_registration) => component_registration,
+                        None => {
+                            on_error(
+                                Some(scene_entity.entity),
+                                DynamicSceneToWorldError::UnregisteredComponent {
+                                    type_name: component.type_name.to_string(),
+                                },
+                            )?;
+                            continue;
+                        }
+                    };
+                if let Err(reason) = component_registration.validate_property(component) {
+                    on_error(
+                        Some(scene_entity.entity),
+                        DynamicSceneToWorldError::InvalidComponent {
+                            type_name: component.type_name.to_string(),
+                            reason,
+                        },
+                    )?;
+                    continue;
+                }
                 if world.has_component_type(new_entity, component_registration.ty) {
                     component_registration.apply_property_to_entity(world, new_entity, component);
                 } else {
                     component_registration
                         .add_property_to_entity(world, resources, new_entity, component);
                 }
+                component_registration.resolve_component(world, resources, new_entity);
             }
         }
 
         for component_registration in component_registry.iter() {
-            component_registration
-                .map_entities(world, &entity_map)
-                .unwrap();
+            if let Err(error) = component_registration.map_entities(world, &entity_map) {
+                on_error(None, error.into())?;
+            }
         }
 
         Ok(())
     }
 
+    /// Despawns every entity in `world` before writing this scene to it. The world's archetype
+    /// storage is preserved, which avoids reallocating when scenes are repeatedly loaded into the
+    /// same world. Resources are stored outside of the world and are left untouched.
+    pub fn write_to_cleared_world(
+        &self,
+        world: &mut World,
+        resources: &Resources,
+    ) -> Result<(), DynamicSceneToWorldError> {
+        world.clear();
+        self.write_to_world(world, resources)
+    }
+
     // TODO: move to AssetSaver when it is implemented
     pub fn serialize_ron(&self, registry: &PropertyTypeRegistry) -> Result<String, ron::Error> {
         serialize_ron(SceneSerializer::new(self, registry))
     }
 
+    /// Like [DynamicScene::serialize_ron], but writes each registered component's doc string as a
+    /// comment right above its type name, which makes hand-edited scene files easier to read.
+    pub fn serialize_ron_documented(
+        &self,
+        registry: &PropertyTypeRegistry,
+        component_registry: &ComponentRegistry,
+    ) -> Result<String, ron::Error> {
+        let ron = self.serialize_ron(registry)?;
+        let components_start = format!("{}: [", ENTITY_FIELD_COMPONENTS);
+        // indent of the open component list. only type names written directly in it get a doc,
+        // not those of nested properties or resources
+        let mut components_indent = None;
+        let mut documented = String::with_capacity(ron.len());
+        for line in ron.lines() {
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+            let mut doc = None;
+            match components_indent {
+                None if trimmed == components_start => components_indent = Some(indent.len()),
+                Some(components) if indent.len() == components && trimmed.starts_with(']') => {
+                    components_indent = None
+                }
+                // component entries are one level below the list, and their fields one more
+                Some(components) if indent.len() == components + 2 * RON_INDENTOR.len() => {
+                    doc = trimmed
+                        .strip_prefix("\"type\": \"")
+                        .and_then(|rest| rest.split('"').next())
+                        .and_then(|type_name| component_registry.get_with_full_name(type_name))
+                        .and_then(|registration| registration.doc);
+                }
+                _ => {}
+            }
+            if let Some(doc) = doc {
+                for doc_line in doc.lines() {
+                    documented.push_str(indent);
+                    documented.push_str("// ");
+                    documented.push_str(doc_line);
+                    documented.push('\n');
+                }
+            }
+            documented.push_str(line);
+            documented.push('\n');
+        }
+        Ok(documented)
+    }
+
+    /// Hashes the serialized form of this scene with [stable_hash]. Scenes built from identically
+    /// constructed worlds produce the same hash, in any run and with any Rust version, which
+    /// makes it cheap to check whether a save actually changed.
+    pub fn content_hash(&self, registry: &PropertyTypeRegistry) -> Result<u64, ron::Error> {
+        Ok(stable_hash(self.serialize_ron(registry)?.as_bytes()))
+    }
+
     pub fn get_scene(&self, resources: &Resources) -> Result<Scene, DynamicSceneToWorldError> {
         let mut world = World::default();
         self.write_to_world(&mut world, resources)?;
@@ -105,13 +391,726 @@ impl DynamicScene {
 pub fn serialize_ron<S>(serialize: S) -> Result<String, ron::Error>
 where
     S: Serialize,
+{
+    let mut buf = Vec::new();
+    serialize_ron_to_writer(&mut buf, serialize)?;
+    Ok(String::from_utf8(buf).unwrap())
+}
+
+const RON_INDENTOR: &str = "  ";
+
+/// Writes the same pretty RON as [serialize_ron] to `writer`.
+pub fn serialize_ron_to_writer<W, S>(writer: W, serialize: S) -> Result<(), ron::Error>
+where
+    W: Write,
+    S: Serialize,
 {
     let pretty_config = ron::ser::PrettyConfig::default()
         .with_decimal_floats(true)
-        .with_indentor("  ".to_string())
+        .with_indentor(RON_INDENTOR.to_string())
         .with_new_line("\n".to_string());
-    let mut buf = Vec::new();
-    let mut ron_serializer = ron::ser::Serializer::new(&mut buf, Some(pretty_config), false)?;
-    serialize.serialize(&mut ron_serializer)?;
-    Ok(String::from_utf8(buf).unwrap())
+    let mut ron_serializer = ron::ser::Serializer::new(writer, Some(pretty_config), false)?;
+    serialize.serialize(&mut ron_serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ComponentErrorPolicy, DynamicScene, DynamicSceneToWorldError, Entity, SpawnSequence,
+    };
+    use crate::{
+        serde::{SceneDeserializer, SceneSerializer},
+        test_support::{
+            build_sample_world, register_sample_components, register_sample_parent,
+            sample_type_registry, SampleParent, SamplePosition, SampleVelocity,
+        },
+    };
+    use bevy_asset::{Handle, HandleId};
+    use bevy_ecs::{FromResources, Mutated, Resources, World};
+    use bevy_property::{DynamicProperties, Properties, PropertyTypeRegistry};
+    use bevy_type_registry::{
+        ComponentRegistration, ComponentRegistry, ResolveFromResources, TypeRegistry, TypeUuid,
+        BLOB_FIELD,
+    };
+    use bevy_utils::{stable_hash, HashMap};
+    use serde::{de::DeserializeSeed, Deserialize, Serialize};
+    use std::{sync::Arc, thread};
+
+    #[derive(Properties, Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+    struct CollisionMesh {
+        vertices: Vec<f32>,
+        indices: Vec<u32>,
+    }
+
+    #[derive(Properties, Default)]
+    struct GameConfig {
+        difficulty: u32,
+        music_volume: f32,
+    }
+
+    #[derive(TypeUuid)]
+    #[uuid = "0f3b4c52-1d7e-4a8e-9a55-3c0d1e8f6a21"]
+    struct TestMesh;
+
+    struct MeshLibrary {
+        cube: Handle<TestMesh>,
+    }
+
+    #[derive(Properties)]
+    struct MeshInstance {
+        scale: f32,
+        #[property(ignore)]
+        mesh: Handle<TestMesh>,
+    }
+
+    impl FromResources for MeshInstance {
+        fn from_resources(resources: &Resources) -> Self {
+            let library = resources.get::<MeshLibrary>().unwrap();
+            MeshInstance {
+                scale: 1.0,
+                mesh: library.cube.clone(),
+            }
+        }
+    }
+
+    struct MockAssetResolver {
+        meshes: HashMap<String, Handle<TestMesh>>,
+    }
+
+    #[derive(Properties, Default)]
+    struct MeshPath {
+        path: String,
+        #[property(ignore)]
+        mesh: Handle<TestMesh>,
+    }
+
+    impl ResolveFromResources for MeshPath {
+        fn resolve_from_resources(&mut self, resources: &Resources) {
+            let resolver = resources.get::<MockAssetResolver>().unwrap();
+            self.mesh = resolver.meshes[&self.path].clone();
+        }
+    }
+
+    fn build_world() -> World {
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        world.spawn((SamplePosition {
+            x: 3.0,
+            y: 4.0,
+            z: 0.0,
+        },));
+        world
+    }
+
+    struct Transient;
+
+    #[test]
+    fn content_hash() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let property_registry = PropertyTypeRegistry::default();
+
+        let world_a = build_world();
+        let mut world_b = build_world();
+        let hash_a = DynamicScene::from_world(&world_a, &component_registry)
+            .content_hash(&property_registry)
+            .unwrap();
+        let hash_b = DynamicScene::from_world(&world_b, &component_registry)
+            .content_hash(&property_registry)
+            .unwrap();
+        assert_eq!(hash_a, hash_b);
+
+        let entity = world_b.query::<bevy_ecs::Entity>().next().unwrap();
+        world_b.get_mut::<SamplePosition>(entity).unwrap().x = 10.0;
+        let hash_b = DynamicScene::from_world(&world_b, &component_registry)
+            .content_hash(&property_registry)
+            .unwrap();
+        assert_ne!(hash_a, hash_b);
+    }
+
+    #[test]
+    fn component_hash_is_stable() {
+        // the hash of the empty component list `[]`, which must not depend on the Rust version
+        let entity = Entity {
+            entity: 0,
+            components: Vec::new(),
+        };
+        assert_eq!(
+            entity
+                .component_hash(&PropertyTypeRegistry::default())
+                .unwrap(),
+            0x0961_2b07_b5ec_b5a5
+        );
+    }
+
+    #[test]
+    fn content_hash_is_stable() {
+        // FNV-1a reference values, which must never change since hashes are saved
+        assert_eq!(stable_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(stable_hash(b"foobar"), 0x8594_4171_f739_67e8);
+
+        let property_registry = PropertyTypeRegistry::default();
+        let scene = DynamicScene::default();
+        let ron = scene.serialize_ron(&property_registry).unwrap();
+        assert_eq!(
+            scene.content_hash(&property_registry).unwrap(),
+            stable_hash(ron.as_bytes())
+        );
+    }
+
+    #[test]
+    fn resource_round_trip() {
+        let type_registry = TypeRegistry::default();
+        type_registry.resource.write().register::<GameConfig>();
+
+        let mut resources = Resources::default();
+        resources.insert(GameConfig {
+            difficulty: 3,
+            music_volume: 0.5,
+        });
+        let mut scene = DynamicScene::default();
+        scene.add_resources(&resources, &type_registry.resource.read());
+        let ron = scene.serialize_ron(&type_registry.property.read()).unwrap();
+
+        let property_type_registry = type_registry.property.read();
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let loaded_scene = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+
+        let mut loaded_resources = Resources::default();
+        loaded_resources.insert(type_registry.clone());
+        loaded_scene.write_resources(&mut loaded_resources).unwrap();
+        let config = loaded_resources.get::<GameConfig>().unwrap();
+        assert_eq!(config.difficulty, 3);
+        assert_eq!(config.music_volume, 0.5);
+    }
+
+    mod other {
+        use bevy_property::Properties;
+
+        #[derive(Properties, Default)]
+        pub struct GameConfig {
+            pub difficulty: u32,
+        }
+    }
+
+    #[test]
+    fn ambiguous_resource() {
+        let type_registry = TypeRegistry::default();
+        type_registry.resource.write().register::<GameConfig>();
+        type_registry
+            .resource
+            .write()
+            .register::<other::GameConfig>();
+        let mut config = DynamicProperties::map();
+        config.type_name = "GameConfig".to_string();
+        let scene = DynamicScene {
+            resources: vec![config],
+            ..Default::default()
+        };
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        match scene.write_resources(&mut resources) {
+            Err(DynamicSceneToWorldError::AmbiguousResource { type_name }) => {
+                assert_eq!(type_name, "GameConfig")
+            }
+            _ => panic!("expected an ambiguous resource error"),
+        }
+    }
+
+    #[test]
+    fn write_to_cleared_world() {
+        let type_registry = sample_type_registry();
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+
+        let first_scene = DynamicScene::from_world(&build_world(), &type_registry.component.read());
+        let mut second_world = World::default();
+        second_world.spawn((SamplePosition {
+            x: 5.0,
+            y: 6.0,
+            z: 0.0,
+        },));
+        let second_scene = DynamicScene::from_world(&second_world, &type_registry.component.read());
+
+        let mut world = World::default();
+        first_scene
+            .write_to_cleared_world(&mut world, &resources)
+            .unwrap();
+        assert_eq!(world.query::<&SamplePosition>().count(), 2);
+
+        second_scene
+            .write_to_cleared_world(&mut world, &resources)
+            .unwrap();
+        let positions = world
+            .query::<&SamplePosition>()
+            .map(|position| (position.x, position.y))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(5.0, 6.0)]);
+    }
+
+    #[test]
+    fn serialize_to_multiple_formats() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let property_type_registry = PropertyTypeRegistry::default();
+        let scene = DynamicScene::from_world(&build_world(), &component_registry);
+
+        let ron = scene.serialize_ron(&property_type_registry).unwrap();
+        let json =
+            serde_json::to_string(&SceneSerializer::new(&scene, &property_type_registry)).unwrap();
+
+        let mut ron_deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let ron_scene = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut ron_deserializer)
+        .unwrap();
+        let mut json_deserializer = serde_json::Deserializer::from_str(&json);
+        let json_scene = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut json_deserializer)
+        .unwrap();
+
+        assert_eq!(ron_scene.entities.len(), 2);
+        assert_eq!(json_scene.entities.len(), 2);
+        let ron_hash = ron_scene.content_hash(&property_type_registry).unwrap();
+        let json_hash = json_scene.content_hash(&property_type_registry).unwrap();
+        assert_eq!(ron_hash, json_hash);
+    }
+
+    #[test]
+    fn skip_unregistered_component() {
+        let type_registry = sample_type_registry();
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+
+        let mut scene = DynamicScene::from_world(&build_world(), &type_registry.component.read());
+        let mut old_component = DynamicProperties::map();
+        old_component.type_name = "OldComponent".to_string();
+        scene.entities[0].components.push(old_component);
+
+        let mut world = World::default();
+        assert!(scene.write_to_world(&mut world, &resources).is_err());
+
+        let mut world = World::default();
+        scene
+            .write_to_world_with_policy(&mut world, &resources, ComponentErrorPolicy::SkipComponent)
+            .unwrap();
+        assert_eq!(world.query::<&SamplePosition>().count(), 2);
+    }
+
+    #[test]
+    fn sample_world_round_trip() {
+        let (world, component_registry) = build_sample_world(0, 30);
+        let type_registry = sample_type_registry();
+        let scene = DynamicScene::from_world(&world, &component_registry);
+        let ron = scene.serialize_ron(&type_registry.property.read()).unwrap();
+
+        let property_type_registry = type_registry.property.read();
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let loaded_scene = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+        let mut loaded_world = World::default();
+        loaded_scene
+            .write_to_world(&mut loaded_world, &resources)
+            .unwrap();
+        assert_eq!(loaded_world.query::<&SamplePosition>().count(), 30);
+        assert_eq!(loaded_world.query::<&SampleVelocity>().count(), 15);
+    }
+
+    #[test]
+    fn serialize_from_two_threads() {
+        let (mut world, _) = build_sample_world(0, 100);
+        let type_registry = sample_type_registry();
+        world.clear_trackers();
+        let world = Arc::new(world);
+        let expected = DynamicScene::from_world(&world, &type_registry.component.read())
+            .serialize_ron(&type_registry.property.read())
+            .unwrap();
+
+        let handles = (0..2)
+            .map(|_| {
+                let world = world.clone();
+                let type_registry = type_registry.clone();
+                thread::spawn(move || {
+                    DynamicScene::from_world(&world, &type_registry.component.read())
+                        .serialize_ron(&type_registry.property.read())
+                        .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+        assert_eq!(world.query::<Mutated<SamplePosition>>().count(), 0);
+        assert_eq!(world.query::<Mutated<SampleVelocity>>().count(), 0);
+    }
+
+    #[test]
+    fn spawn_order() {
+        let type_registry = sample_type_registry();
+        type_registry.component.write().register::<SpawnSequence>();
+        let mut world = World::default();
+        let spawn = |world: &mut World, index: u64| {
+            let position = SamplePosition {
+                x: index as f32,
+                y: 0.0,
+                z: 0.0,
+            };
+            world.spawn((position, SpawnSequence { index }))
+        };
+        let entities = (0..4).map(|i| spawn(&mut world, i)).collect::<Vec<_>>();
+        // despawning swaps the last entity of the archetype into the freed slot, and the next
+        // entity reuses the freed id
+        world.despawn(entities[1]).unwrap();
+        let recycled = spawn(&mut world, 4);
+        assert_eq!(recycled.id(), entities[1].id());
+        let iteration_order = |world: &World| {
+            world
+                .query::<&SamplePosition>()
+                .map(|position| position.x)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(iteration_order(&world), vec![0.0, 3.0, 2.0, 4.0]);
+
+        let scene =
+            DynamicScene::from_world_in_spawn_order(&world, &type_registry.component.read());
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+        let mut loaded_world = World::default();
+        scene.write_to_world(&mut loaded_world, &resources).unwrap();
+        let spawn_order = vec![0.0, 2.0, 3.0, 4.0];
+        assert_eq!(iteration_order(&loaded_world), spawn_order);
+
+        // the sequence is saved with the scene, so saving the loaded world keeps the order
+        let scene =
+            DynamicScene::from_world_in_spawn_order(&loaded_world, &type_registry.component.read());
+        let mut reloaded_world = World::default();
+        scene
+            .write_to_world(&mut reloaded_world, &resources)
+            .unwrap();
+        assert_eq!(iteration_order(&reloaded_world), spawn_order);
+        let sequences = reloaded_world
+            .query::<&SpawnSequence>()
+            .map(|sequence| sequence.index)
+            .collect::<Vec<_>>();
+        assert_eq!(sequences, vec![0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn from_resources_factory() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().register::<MeshInstance>();
+        let cube = Handle::<TestMesh>::weak(HandleId::random::<TestMesh>());
+        let mut resources = Resources::default();
+        resources.insert(MeshLibrary { cube: cube.clone() });
+
+        let mut world = World::default();
+        world.spawn((MeshInstance {
+            scale: 2.0,
+            mesh: Handle::default(),
+        },));
+        let scene = DynamicScene::from_world(&world, &type_registry.component.read());
+        resources.insert(type_registry);
+
+        let mut loaded_world = World::default();
+        scene.write_to_world(&mut loaded_world, &resources).unwrap();
+        let instance = loaded_world.query::<&MeshInstance>().next().unwrap();
+        assert_eq!(instance.scale, 2.0);
+        assert_eq!(instance.mesh, cube);
+    }
+
+    #[test]
+    fn resolve_asset_path() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().add_registration(
+            ComponentRegistration::build::<MeshPath>()
+                .resolve_from_resources()
+                .finish(),
+        );
+        let cube = Handle::<TestMesh>::weak(HandleId::random::<TestMesh>());
+        let mut meshes = HashMap::default();
+        meshes.insert("models/cube.gltf".to_string(), cube.clone());
+
+        let mut world = World::default();
+        world.spawn((MeshPath {
+            path: "models/cube.gltf".to_string(),
+            mesh: Handle::weak(HandleId::random::<TestMesh>()),
+        },));
+        let ron = DynamicScene::from_world(&world, &type_registry.component.read())
+            .serialize_ron(&type_registry.property.read())
+            .unwrap();
+        assert!(ron.contains("models/cube.gltf"));
+
+        let property_type_registry = type_registry.property.read();
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let scene = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+        resources.insert(MockAssetResolver { meshes });
+        let mut loaded_world = World::default();
+        scene.write_to_world(&mut loaded_world, &resources).unwrap();
+        let mesh_path = loaded_world.query::<&MeshPath>().next().unwrap();
+        assert_eq!(mesh_path.mesh, cube);
+    }
+
+    #[test]
+    fn collect_errors() {
+        let type_registry = sample_type_registry();
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+
+        let component = |type_name: &str, x: f32| {
+            let mut properties = DynamicProperties::map();
+            properties.type_name = type_name.to_string();
+            properties.set("x", x);
+            properties
+        };
+        let position = std::any::type_name::<SamplePosition>();
+        let scene = DynamicScene {
+            entities: vec![
+                Entity {
+                    entity: 0,
+                    components: vec![component(position, 1.0)],
+                },
+                Entity {
+                    entity: 1,
+                    components: vec![component("Missing", 0.0), component(position, 2.0)],
+                },
+                Entity {
+                    entity: 2,
+                    components: vec![component(position, 3.0)],
+                },
+                Entity {
+                    entity: 3,
+                    components: vec![component("AlsoMissing", 0.0)],
+                },
+            ],
+            resources: Vec::new(),
+            metadata: None,
+        };
+
+        let mut world = World::default();
+        let errors = scene.write_to_world_collecting_errors(&mut world, &resources);
+        let reported = errors
+            .iter()
+            .map(|(entity, error)| match error {
+                DynamicSceneToWorldError::UnregisteredComponent { type_name } => {
+                    (*entity, type_name.as_str())
+                }
+                _ => panic!("unexpected error {}", error),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reported,
+            vec![(Some(1), "Missing"), (Some(3), "AlsoMissing")]
+        );
+
+        let mut positions = world
+            .query::<&SamplePosition>()
+            .map(|position| position.x)
+            .collect::<Vec<_>>();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(positions, vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn collecting_errors_dangling_parent() {
+        let type_registry = sample_type_registry();
+        register_sample_parent(&mut type_registry.component.write());
+        let mut world = World::default();
+        let outside = world.spawn(());
+        world.spawn((SamplePosition::default(), SampleParent(outside)));
+        world.despawn(outside).unwrap();
+        let scene = DynamicScene::from_world(&world, &type_registry.component.read());
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+
+        let mut loaded_world = World::default();
+        let errors = scene.write_to_world_collecting_errors(&mut loaded_world, &resources);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0],
+            (None, DynamicSceneToWorldError::MapEntities(_))
+        ));
+        assert_eq!(loaded_world.query::<&SamplePosition>().count(), 1);
+
+        let mut loaded_world = World::default();
+        assert!(matches!(
+            scene.write_to_world(&mut loaded_world, &resources),
+            Err(DynamicSceneToWorldError::MapEntities(_))
+        ));
+    }
+
+    #[test]
+    fn blob_component_round_trip() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().add_registration(
+            ComponentRegistration::build::<CollisionMesh>()
+                .blob()
+                .finish(),
+        );
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+
+        let mesh = CollisionMesh {
+            vertices: vec![0.0, 1.0, 0.5, 2.0],
+            indices: vec![0, 1, 2, 1, 2, 3],
+        };
+        let mut world = World::default();
+        world.spawn((mesh.clone(),));
+        let scene = DynamicScene::from_world(&world, &type_registry.component.read());
+        let ron = scene.serialize_ron(&type_registry.property.read()).unwrap();
+        assert!(ron.contains(BLOB_FIELD));
+        assert!(!ron.contains("vertices"));
+
+        let property_type_registry = type_registry.property.read();
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let loaded_scene = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+
+        let mut loaded_world = World::default();
+        loaded_scene
+            .write_to_world(&mut loaded_world, &resources)
+            .unwrap();
+        let loaded = loaded_world.query::<&CollisionMesh>().next().cloned();
+        assert_eq!(loaded, Some(mesh));
+    }
+
+    #[test]
+    fn corrupted_blob_component() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().add_registration(
+            ComponentRegistration::build::<CollisionMesh>()
+                .blob()
+                .finish(),
+        );
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+
+        let mut blob = DynamicProperties::map();
+        blob.type_name = std::any::type_name::<CollisionMesh>().to_string();
+        blob.set(BLOB_FIELD, "truncated".to_string());
+        let scene = DynamicScene {
+            entities: vec![Entity {
+                entity: 0,
+                components: vec![blob],
+            }],
+            resources: Vec::new(),
+            metadata: None,
+        };
+
+        let mut world = World::default();
+        let result = scene.write_to_world(&mut world, &resources);
+        assert!(matches!(
+            result,
+            Err(DynamicSceneToWorldError::InvalidComponent { .. })
+        ));
+        assert_eq!(world.query::<&CollisionMesh>().count(), 0);
+    }
+
+    #[test]
+    fn documented_ron() {
+        let type_registry = TypeRegistry::default();
+        type_registry
+            .component
+            .write()
+            .register_with_doc::<SamplePosition>("World position in meters.");
+        let mut scene = DynamicScene::from_world(&build_world(), &type_registry.component.read());
+        // a resource of the same type is not a component entry and gets no doc
+        scene.resources.push(
+            SamplePosition {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            }
+            .to_dynamic(),
+        );
+
+        let ron = scene
+            .serialize_ron_documented(
+                &type_registry.property.read(),
+                &type_registry.component.read(),
+            )
+            .unwrap();
+        let lines = ron.lines().collect::<Vec<_>>();
+        let comment = lines
+            .iter()
+            .position(|line| line.trim() == "// World position in meters.")
+            .unwrap();
+        assert!(lines[comment + 1].contains(std::any::type_name::<SamplePosition>()));
+        let comments = lines
+            .iter()
+            .filter(|line| line.trim() == "// World position in meters.")
+            .count();
+        assert_eq!(comments, 2);
+
+        let property_type_registry = type_registry.property.read();
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let loaded_scene = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+        assert_eq!(
+            loaded_scene.serialize_ron(&property_type_registry).unwrap(),
+            scene.serialize_ron(&property_type_registry).unwrap()
+        );
+    }
+
+    #[test]
+    fn from_world_excluding() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let mut world = World::default();
+        let saved = world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        world.spawn((
+            SamplePosition {
+                x: 3.0,
+                y: 4.0,
+                z: 0.0,
+            },
+            Transient,
+        ));
+        world.spawn((Transient,));
+
+        let scene = DynamicScene::from_world_excluding::<Transient>(&world, &component_registry);
+        assert_eq!(scene.entities.len(), 1);
+        assert_eq!(scene.entities[0].entity, saved.id());
+        assert_eq!(
+            DynamicScene::from_world(&world, &component_registry)
+                .entities
+                .len(),
+            3
+        );
+    }
 }
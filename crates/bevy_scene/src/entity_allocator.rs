@@ -0,0 +1,120 @@
+use crate::DynamicScene;
+use bevy_ecs::Entity;
+use bevy_utils::HashMap;
+
+/// Tracks the entity ids stored in a [DynamicScene]. Ids handed out by
+/// [SceneEntityAllocator::allocate] are never reused, and every time a stored id shows up again
+/// its generation is bumped, so scenes containing recycled ids still produce a distinct scene
+/// [Entity] per stored entity.
+#[derive(Debug, Default, Clone)]
+pub struct SceneEntityAllocator {
+    next_id: u32,
+    generations: HashMap<u32, u32>,
+}
+
+impl SceneEntityAllocator {
+    /// Records a stored entity id and returns the scene entity it refers to. The first occurrence
+    /// of an id has generation 0, which is what entity references inside components point to.
+    pub fn register(&mut self, id: u32) -> Entity {
+        let generation = match self.generations.get_mut(&id) {
+            Some(generation) => {
+                *generation += 1;
+                *generation
+            }
+            None => {
+                self.generations.insert(id, 0);
+                0
+            }
+        };
+        self.next_id = self.next_id.max(id.saturating_add(1));
+        Entity::from_bits(u64::from(generation) << 32 | u64::from(id))
+    }
+
+    /// Returns an id that isn't used by any registered entity.
+    pub fn allocate(&mut self) -> u32 {
+        let id = self.next_id;
+        self.register(id);
+        id
+    }
+
+    pub fn next_id(&self) -> u32 {
+        self.next_id
+    }
+
+    /// The most recent generation of `id`, or `None` if it was never registered.
+    pub fn generation(&self, id: u32) -> Option<u32> {
+        self.generations.get(&id).cloned()
+    }
+}
+
+impl DynamicScene {
+    /// Returns an allocator that has registered every entity in this scene, which can be used to
+    /// pick ids for entities added to the scene later.
+    pub fn entity_allocator(&self) -> SceneEntityAllocator {
+        let mut allocator = SceneEntityAllocator::default();
+        for entity in self.entities.iter() {
+            allocator.register(entity.entity);
+        }
+        allocator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DynamicScene, Entity};
+    use bevy_ecs::{Resources, World};
+    use bevy_property::{DynamicProperties, Properties};
+    use bevy_type_registry::TypeRegistry;
+    use bevy_utils::HashSet;
+
+    #[derive(Properties, Default)]
+    struct Marker {
+        value: u32,
+    }
+
+    fn entity(id: u32, value: u32) -> Entity {
+        let mut marker = DynamicProperties::map();
+        marker.type_name = std::any::type_name::<Marker>().to_string();
+        marker.set("value", value);
+        Entity {
+            entity: id,
+            components: vec![marker],
+        }
+    }
+
+    #[test]
+    fn recycled_ids() {
+        let scene = DynamicScene {
+            entities: vec![entity(0, 1), entity(1, 2), entity(0, 3)],
+            resources: Vec::new(),
+            metadata: None,
+        };
+
+        let mut allocator = scene.entity_allocator();
+        assert_eq!(allocator.generation(0), Some(1));
+        assert_eq!(allocator.generation(1), Some(0));
+        assert_eq!(allocator.allocate(), 2);
+        assert_eq!(allocator.allocate(), 3);
+
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().register::<Marker>();
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut world = World::default();
+        scene.write_to_world(&mut world, &resources).unwrap();
+
+        let loaded = world
+            .query::<(bevy_ecs::Entity, &Marker)>()
+            .map(|(entity, marker)| (entity, marker.value))
+            .collect::<Vec<_>>();
+        assert_eq!(loaded.len(), 3);
+        let entities = loaded
+            .iter()
+            .map(|(entity, _)| *entity)
+            .collect::<HashSet<_>>();
+        assert_eq!(entities.len(), 3);
+        let mut values = loaded.iter().map(|(_, value)| *value).collect::<Vec<_>>();
+        values.sort();
+        assert_eq!(values, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    serde::{EntitySerializer, SceneEntityDeserializer},
+    DynamicScene, Entity, SceneError,
+};
+use bevy_ecs::{Entity as WorldEntity, World};
+use bevy_property::PropertyTypeRegistry;
+use bevy_type_registry::ComponentRegistry;
+use serde::de::DeserializeSeed;
+use std::io::Write;
+
+/// Records entities as they are spawned into an append-only log, one JSON line per spawn. The
+/// log can be replayed with [DynamicScene::from_spawn_log].
+#[derive(Debug, Default)]
+pub struct IncrementalSceneWriter {
+    log: Vec<u8>,
+    spawn_count: usize,
+}
+
+impl IncrementalSceneWriter {
+    /// Appends the registered components `entity` currently has to the log. Call this right
+    /// after the entity is spawned.
+    pub fn record_spawn(
+        &mut self,
+        world: &World,
+        entity: WorldEntity,
+        component_registry: &ComponentRegistry,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<(), SceneError> {
+        let location = world
+            .get_entity_location(entity)
+            .ok_or_else(|| SceneError::NoSuchEntity(entity.id()))?;
+        let archetype = world.archetypes().nth(location.archetype as usize).unwrap();
+        let components = archetype
+            .types()
+            .iter()
+            .filter_map(|type_info| component_registry.get(&type_info.id()))
+            .map(|registration| {
+                registration
+                    .get_serialized_component(archetype, location.index as usize)
+                    .map_err(|reason| SceneError::UnserializableComponent {
+                        type_name: registration.long_name.to_string(),
+                        reason,
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        let entity = Entity {
+            entity: entity.id(),
+            components,
+        };
+
+        serde_json::to_writer(
+            &mut self.log,
+            &EntitySerializer {
+                entity: &entity,
+                registry,
+            },
+        )?;
+        self.log.write_all(b"\n")?;
+        self.spawn_count += 1;
+        Ok(())
+    }
+
+    pub fn spawn_count(&self) -> usize {
+        self.spawn_count
+    }
+
+    pub fn log(&self) -> &[u8] {
+        &self.log
+    }
+
+    pub fn into_log(self) -> Vec<u8> {
+        self.log
+    }
+}
+
+impl DynamicScene {
+    /// Replays a log written by [IncrementalSceneWriter], returning a scene with the recorded
+    /// entities in spawn order. The log may still be growing: a partially written last line is
+    /// an error, so only pass complete lines.
+    pub fn from_spawn_log(
+        log: &[u8],
+        registry: &PropertyTypeRegistry,
+    ) -> Result<DynamicScene, SceneError> {
+        let mut scene = DynamicScene::default();
+        for line in log.split(|byte| *byte == b'\n') {
+            if line.is_empty() {
+                continue;
+            }
+            let entity = SceneEntityDeserializer {
+                property_type_registry: registry,
+            }
+            .deserialize(&mut serde_json::Deserializer::from_slice(line))?;
+            scene.entities.push(entity);
+        }
+        Ok(scene)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IncrementalSceneWriter;
+    use crate::{
+        test_support::{sample_type_registry, SampleName, SamplePosition},
+        DynamicScene,
+    };
+    use bevy_ecs::{Resources, World};
+
+    #[test]
+    fn record_and_replay() {
+        let type_registry = sample_type_registry();
+        let mut writer = IncrementalSceneWriter::default();
+        let mut world = World::default();
+        for i in 0..3 {
+            let entity = if i == 1 {
+                world.spawn((
+                    SamplePosition {
+                        x: 1.0,
+                        y: 2.0,
+                        z: 0.0,
+                    },
+                    SampleName {
+                        value: "player".to_string(),
+                    },
+                ))
+            } else {
+                world.spawn((SamplePosition {
+                    x: i as f32,
+                    y: 0.0,
+                    z: 0.0,
+                },))
+            };
+            writer
+                .record_spawn(
+                    &world,
+                    entity,
+                    &type_registry.component.read(),
+                    &type_registry.property.read(),
+                )
+                .unwrap();
+        }
+        assert_eq!(writer.spawn_count(), 3);
+
+        let scene =
+            DynamicScene::from_spawn_log(writer.log(), &type_registry.property.read()).unwrap();
+        assert_eq!(scene.entities.len(), 3);
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut replayed = World::default();
+        scene.write_to_world(&mut replayed, &resources).unwrap();
+
+        let mut positions = replayed
+            .query::<&SamplePosition>()
+            .map(|position| (position.x, position.y))
+            .collect::<Vec<_>>();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(positions, vec![(0.0, 0.0), (1.0, 2.0), (2.0, 0.0)]);
+        let (position, name) = replayed
+            .query::<(&SamplePosition, &SampleName)>()
+            .next()
+            .unwrap();
+        assert_eq!(
+            *position,
+            SamplePosition {
+                x: 1.0,
+                y: 2.0,
+                z: 0.0
+            }
+        );
+        assert_eq!(name.value, "player");
+    }
+}
@@ -1,15 +1,53 @@
 mod command;
+mod component_column;
+mod copy_world;
 mod dynamic_scene;
+mod entity_allocator;
+mod incremental_scene_writer;
+mod pooled_serializer;
 mod scene;
+mod scene_assembler;
+mod scene_coverage;
+mod scene_diff;
+mod scene_file;
+mod scene_grid;
 mod scene_loader;
+mod scene_metadata;
+#[cfg(feature = "mmap")]
+mod scene_mmap;
+mod scene_ndjson;
+mod scene_palette;
+mod scene_probe;
 mod scene_spawner;
+mod scene_template;
 pub mod serde;
+mod serialization_cache;
+mod spawn_command;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+mod type_name_interner;
 
 pub use command::*;
+pub use component_column::*;
+pub use copy_world::*;
 pub use dynamic_scene::*;
+pub use entity_allocator::*;
+pub use incremental_scene_writer::*;
+pub use pooled_serializer::*;
 pub use scene::*;
+pub use scene_coverage::*;
+pub use scene_diff::*;
+pub use scene_file::*;
+pub use scene_grid::*;
 pub use scene_loader::*;
+pub use scene_metadata::*;
+pub use scene_ndjson::*;
+pub use scene_probe::*;
 pub use scene_spawner::*;
+pub use scene_template::*;
+pub use serialization_cache::*;
+pub use spawn_command::*;
+pub use type_name_interner::*;
 
 pub mod prelude {
     pub use crate::{DynamicScene, Scene, SceneSpawner, SpawnSceneCommands};
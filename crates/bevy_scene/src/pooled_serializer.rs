@@ -0,0 +1,64 @@
+use crate::{serde::SceneSerializer, serialize_ron_to_writer, DynamicScene};
+use bevy_property::PropertyTypeRegistry;
+
+/// Serializes scenes to RON into an output buffer that is kept between calls, so code that saves
+/// a snapshot every frame doesn't allocate a new buffer each time.
+#[derive(Debug, Default)]
+pub struct PooledSceneSerializer {
+    buffer: Vec<u8>,
+}
+
+impl PooledSceneSerializer {
+    /// Starts with room for `capacity` bytes of output, e.g. the size of a previous snapshot.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buffer: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Serializes `scene` in the same format as [DynamicScene::serialize_ron]. The returned text
+    /// is overwritten by the next call.
+    pub fn serialize_into(
+        &mut self,
+        scene: &DynamicScene,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<&str, ron::Error> {
+        self.buffer.clear();
+        serialize_ron_to_writer(&mut self.buffer, SceneSerializer::new(scene, registry))?;
+        Ok(std::str::from_utf8(&self.buffer).unwrap())
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PooledSceneSerializer;
+    use crate::test_support::{build_sample_scene, sample_type_registry};
+
+    #[test]
+    fn reuses_buffer() {
+        let scene = build_sample_scene(0, 50);
+        let type_registry = sample_type_registry();
+        let property_type_registry = type_registry.property.read();
+        let expected = scene.serialize_ron(&property_type_registry).unwrap();
+
+        let mut serializer = PooledSceneSerializer::default();
+        assert_eq!(
+            serializer
+                .serialize_into(&scene, &property_type_registry)
+                .unwrap(),
+            expected
+        );
+        let capacity = serializer.capacity();
+        assert_eq!(
+            serializer
+                .serialize_into(&scene, &property_type_registry)
+                .unwrap(),
+            expected
+        );
+        assert_eq!(serializer.capacity(), capacity);
+    }
+}
@@ -1,5 +1,24 @@
-use bevy_ecs::World;
-use bevy_type_registry::TypeUuid;
+use crate::{DynamicScene, DynamicSceneToWorldError, Entity};
+use bevy_ecs::{Component, Entity as WorldEntity, EntityMap, MapEntitiesError, Resources, World};
+use bevy_type_registry::{TypeRegistry, TypeUuid};
+
+/// Determines how [Scene::extract] handles references to entities that aren't extracted.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DanglingReferencePolicy {
+    /// Stop extracting and return the error.
+    Fail,
+    /// Log the error and leave the referencing component out of the extracted entity.
+    DropComponent,
+    /// Keep the component. Its reference points at an entity that doesn't exist in the
+    /// extracted scene. References to entities that aren't in the scene at all still fail.
+    KeepDangling,
+}
+
+impl Default for DanglingReferencePolicy {
+    fn default() -> Self {
+        DanglingReferencePolicy::Fail
+    }
+}
 
 #[derive(Debug, TypeUuid)]
 #[uuid = "c156503c-edd9-4ec7-8d33-dab392df03cd"]
@@ -11,4 +30,525 @@ impl Scene {
     pub fn new(world: World) -> Self {
         Self { world }
     }
+
+    /// Builds a scene from dynamic scene entities, such as ones produced by deserializing a scene
+    /// file and then edited by a migration tool. Components are looked up in the [TypeRegistry]
+    /// resource.
+    pub fn from_dynamic(
+        entities: Vec<Entity>,
+        resources: &Resources,
+    ) -> Result<Self, DynamicSceneToWorldError> {
+        DynamicScene {
+            entities,
+            resources: Vec::new(),
+            metadata: None,
+        }
+        .get_scene(resources)
+    }
+
+    /// Despawns every entity so the scene can be reused. Archetype storage is kept for the next
+    /// load, while the removed-component trackers are reset so they don't grow across reuses.
+    pub fn clear(&mut self) {
+        self.world.clear();
+        self.world.clear_trackers();
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.world
+            .archetypes()
+            .map(|archetype| archetype.len())
+            .sum()
+    }
+
+    /// Counts the entities in the scene that have a `T` component.
+    pub fn count_components<T: Component>(&self) -> usize {
+        self.world
+            .archetypes()
+            .filter(|archetype| archetype.has::<T>())
+            .map(|archetype| archetype.len())
+            .sum()
+    }
+
+    /// Runs `f` once for every entity in the scene, e.g. to apply a fix-up after loading. The
+    /// entities are collected up front, so `f` is free to add or remove components.
+    pub fn for_each_entity_mut(&mut self, mut f: impl FnMut(&mut World, WorldEntity)) {
+        let entities = self.world.query::<WorldEntity>().collect::<Vec<_>>();
+        for entity in entities {
+            f(&mut self.world, entity);
+        }
+    }
+
+    /// Removes every component that isn't registered in the [TypeRegistry] resource, so the
+    /// scene's world only contains what would be saved. Entities without any registered component
+    /// are despawned when `despawn_empty` is set. Entity ids are reassigned in the process; entity
+    /// references are fixed up through each registration's `map_entities`. If a reference points
+    /// at a despawned entity, the scene is left unchanged and the reference is returned as
+    /// [MapEntitiesError::EntityNotFound].
+    pub fn retain_registered(
+        &mut self,
+        resources: &Resources,
+        despawn_empty: bool,
+    ) -> Result<(), MapEntitiesError> {
+        let type_registry = resources.get::<TypeRegistry>().unwrap();
+        let component_registry = type_registry.component.read();
+        let mut world = World::default();
+        let mut entity_map = EntityMap::default();
+        for archetype in self.world.archetypes() {
+            let registrations = archetype
+                .types()
+                .iter()
+                .filter_map(|type_info| component_registry.get(&type_info.id()))
+                .collect::<Vec<_>>();
+            if registrations.is_empty() && despawn_empty {
+                continue;
+            }
+
+            for scene_entity in archetype.iter_entities() {
+                let entity = world.spawn(());
+                entity_map.insert(*scene_entity, entity);
+                for component_registration in registrations.iter() {
+                    component_registration.component_copy(
+                        &self.world,
+                        &mut world,
+                        resources,
+                        *scene_entity,
+                        entity,
+                    );
+                }
+            }
+        }
+
+        for component_registration in component_registry.iter() {
+            component_registration.map_entities(&mut world, &entity_map)?;
+        }
+
+        self.world = world;
+        Ok(())
+    }
+
+    /// Builds a new scene holding only the entities with the given ids and their registered
+    /// components, e.g. to copy an editor selection. `policy` decides what happens to references
+    /// to entities outside of `entities`.
+    pub fn extract(
+        &self,
+        entities: &[u32],
+        resources: &Resources,
+        policy: DanglingReferencePolicy,
+    ) -> Result<Scene, MapEntitiesError> {
+        let type_registry = resources.get::<TypeRegistry>().unwrap();
+        let component_registry = type_registry.component.read();
+        let mut world = World::default();
+        let mut entity_map = EntityMap::default();
+        for scene_entity in self.world.query::<WorldEntity>() {
+            if entities.contains(&scene_entity.id()) {
+                entity_map.insert(scene_entity, world.spawn(()));
+            }
+        }
+
+        // references to the other entities point at placeholders that are despawned once the
+        // references are mapped
+        let mut placeholders = Vec::new();
+        if policy == DanglingReferencePolicy::KeepDangling {
+            for scene_entity in self.world.query::<WorldEntity>() {
+                if entity_map.get(scene_entity).is_err() {
+                    let placeholder = world.spawn(());
+                    entity_map.insert(scene_entity, placeholder);
+                    placeholders.push(placeholder);
+                }
+            }
+        }
+
+        let mut probe_world = World::default();
+        for archetype in self.world.archetypes() {
+            let registrations = archetype
+                .types()
+                .iter()
+                .filter_map(|type_info| component_registry.get(&type_info.id()))
+                .collect::<Vec<_>>();
+            for scene_entity in archetype.iter_entities() {
+                if !entities.contains(&scene_entity.id()) {
+                    continue;
+                }
+
+                let entity = entity_map.get(*scene_entity)?;
+                for component_registration in registrations.iter() {
+                    if policy == DanglingReferencePolicy::DropComponent {
+                        // map a copy of the component on its own to find out whether all of its
+                        // references can be mapped
+                        let probe = probe_world.spawn(());
+                        component_registration.component_copy(
+                            &self.world,
+                            &mut probe_world,
+                            resources,
+                            *scene_entity,
+                            probe,
+                        );
+                        let result =
+                            component_registration.map_entities(&mut probe_world, &entity_map);
+                        probe_world.despawn(probe).unwrap();
+                        if let Err(error) = result {
+                            log::warn!(
+                                "Dropping {} of extracted entity {}: {}",
+                                component_registration.short_name,
+                                scene_entity.id(),
+                                error
+                            );
+                            continue;
+                        }
+                    }
+                    component_registration.component_copy(
+                        &self.world,
+                        &mut world,
+                        resources,
+                        *scene_entity,
+                        entity,
+                    );
+                }
+            }
+        }
+
+        for component_registration in component_registry.iter() {
+            component_registration.map_entities(&mut world, &entity_map)?;
+        }
+        for placeholder in placeholders {
+            world.despawn(placeholder).unwrap();
+        }
+
+        Ok(Scene::new(world))
+    }
+
+    /// Checks that every entity has all of the components with the given short or full type
+    /// names, which are looked up in the [TypeRegistry] resource. Returns the ids of the entities
+    /// missing any of them, in id order. A name that isn't registered can't be present, so every
+    /// entity is reported for it.
+    pub fn require_components(
+        &self,
+        component_names: &[&str],
+        resources: &Resources,
+    ) -> Result<(), Vec<u32>> {
+        let type_registry = resources.get::<TypeRegistry>().unwrap();
+        let component_registry = type_registry.component.read();
+        let required = component_names
+            .iter()
+            .map(|name| {
+                component_registry
+                    .get_with_name(name)
+                    .map(|registration| registration.ty)
+            })
+            .collect::<Vec<_>>();
+        let mut missing = self
+            .world
+            .archetypes()
+            .filter(|archetype| {
+                !required
+                    .iter()
+                    .all(|ty| ty.map_or(false, |ty| archetype.has_type(ty)))
+            })
+            .flat_map(|archetype| archetype.iter_entities().map(|entity| entity.id()))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            missing.sort_unstable();
+            Err(missing)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DanglingReferencePolicy, Scene};
+    use crate::{
+        test_support::{
+            register_sample_parent, sample_type_registry, SampleParent, SamplePosition,
+        },
+        Entity as DynamicEntity,
+    };
+    use bevy_ecs::{Entity, MapEntitiesError, Resources, World};
+    use bevy_property::DynamicProperties;
+
+    struct Transient;
+
+    fn build_scene() -> Scene {
+        let mut world = World::default();
+        world.spawn((
+            SamplePosition {
+                x: 1.0,
+                y: 2.0,
+                z: 0.0,
+            },
+            Transient,
+        ));
+        world.spawn((Transient,));
+        Scene::new(world)
+    }
+
+    #[test]
+    fn retain_registered() {
+        let type_registry = sample_type_registry();
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+
+        let mut scene = build_scene();
+        scene.retain_registered(&resources, false).unwrap();
+        assert_eq!(scene.world.query::<&Transient>().count(), 0);
+        assert_eq!(scene.world.query::<&SamplePosition>().count(), 1);
+        assert_eq!(scene.world.query::<Entity>().count(), 2);
+
+        let mut scene = build_scene();
+        scene.retain_registered(&resources, true).unwrap();
+        assert_eq!(scene.world.query::<&Transient>().count(), 0);
+        let positions = scene
+            .world
+            .query::<&SamplePosition>()
+            .map(|position| (position.x, position.y))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(1.0, 2.0)]);
+        assert_eq!(scene.world.query::<Entity>().count(), 1);
+    }
+
+    #[test]
+    fn retain_registered_dangling_reference() {
+        let type_registry = sample_type_registry();
+        register_sample_parent(&mut type_registry.component.write());
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+
+        let mut world = World::default();
+        let empty = world.spawn((Transient,));
+        world.spawn((SamplePosition::default(), SampleParent(empty)));
+        let mut scene = Scene::new(world);
+
+        let result = scene.retain_registered(&resources, true);
+        assert!(matches!(result, Err(MapEntitiesError::EntityNotFound(entity)) if entity == empty));
+        assert_eq!(scene.entity_count(), 2);
+        assert_eq!(scene.count_components::<Transient>(), 1);
+
+        scene.retain_registered(&resources, false).unwrap();
+        let (parent, _) = scene
+            .world
+            .query::<(&SampleParent, &SamplePosition)>()
+            .next()
+            .unwrap();
+        // the empty entity is kept, so the reference still points at it
+        assert!(scene.world.get_entity_location(parent.0).is_some());
+        assert_eq!(scene.entity_count(), 2);
+    }
+
+    #[test]
+    fn from_dynamic() {
+        let type_registry = sample_type_registry();
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+
+        let mut position = DynamicProperties::map();
+        position.type_name = std::any::type_name::<SamplePosition>().to_string();
+        position.set("x", 3.0f32);
+        position.set("y", 4.0f32);
+        let entities = vec![DynamicEntity {
+            entity: 7,
+            components: vec![position],
+        }];
+
+        let scene = Scene::from_dynamic(entities, &resources).unwrap();
+        let positions = scene
+            .world
+            .query::<&SamplePosition>()
+            .map(|position| (position.x, position.y))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(3.0, 4.0)]);
+    }
+
+    #[test]
+    fn clear() {
+        let mut scene = build_scene();
+        assert_eq!(scene.entity_count(), 2);
+        scene.clear();
+        assert_eq!(scene.entity_count(), 0);
+        assert_eq!(scene.world.query::<&Transient>().count(), 0);
+
+        scene.world.spawn((SamplePosition {
+            x: 1.0,
+            y: 1.0,
+            z: 0.0,
+        },));
+        assert_eq!(scene.entity_count(), 1);
+    }
+
+    #[test]
+    fn count_components() {
+        let scene = build_scene();
+        assert_eq!(scene.count_components::<SamplePosition>(), 1);
+        assert_eq!(scene.count_components::<Transient>(), 2);
+        assert_eq!(scene.count_components::<Entity>(), 0);
+    }
+
+    #[test]
+    fn for_each_entity_mut() {
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        world.spawn((SamplePosition {
+            x: -1.0,
+            y: 0.0,
+            z: 0.0,
+        },));
+        world.spawn((Transient,));
+        let mut scene = Scene::new(world);
+
+        let mut visited = 0;
+        scene.for_each_entity_mut(|world, entity| {
+            visited += 1;
+            if let Ok(mut position) = world.get_mut::<SamplePosition>(entity) {
+                position.x += 10.0;
+            }
+        });
+        assert_eq!(visited, 3);
+        let mut positions = scene
+            .world
+            .query::<&SamplePosition>()
+            .map(|position| (position.x, position.y))
+            .collect::<Vec<_>>();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(positions, vec![(9.0, 0.0), (11.0, 2.0)]);
+    }
+
+    #[test]
+    fn extract() {
+        let type_registry = sample_type_registry();
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+
+        let mut world = World::default();
+        let ids = (0..4)
+            .map(|i| {
+                world
+                    .spawn((
+                        SamplePosition {
+                            x: i as f32,
+                            y: 0.0,
+                            z: 0.0,
+                        },
+                        Transient,
+                    ))
+                    .id()
+            })
+            .collect::<Vec<_>>();
+        let scene = Scene::new(world);
+
+        let extracted = scene
+            .extract(&[ids[1], ids[3]], &resources, DanglingReferencePolicy::Fail)
+            .unwrap();
+        assert_eq!(extracted.entity_count(), 2);
+        assert_eq!(extracted.count_components::<Transient>(), 0);
+        let mut xs = extracted
+            .world
+            .query::<&SamplePosition>()
+            .map(|position| position.x)
+            .collect::<Vec<_>>();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(xs, vec![1.0, 3.0]);
+    }
+
+    #[test]
+    fn extract_dangling_references() {
+        let type_registry = sample_type_registry();
+        register_sample_parent(&mut type_registry.component.write());
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+
+        let mut world = World::default();
+        let position = |x: f32| SamplePosition { x, y: 0.0, z: 0.0 };
+        let root = world.spawn((position(0.0),));
+        let child = world.spawn((position(1.0), SampleParent(root)));
+        let grandchild = world.spawn((position(2.0), SampleParent(child)));
+        let scene = Scene::new(world);
+        let selection = [child.id(), grandchild.id()];
+        let parent_x = |scene: &Scene, parent: Entity| {
+            scene
+                .world
+                .get::<SamplePosition>(parent)
+                .map(|position| position.x)
+                .ok()
+        };
+
+        let result = scene.extract(&selection, &resources, DanglingReferencePolicy::Fail);
+        assert!(matches!(
+            result,
+            Err(MapEntitiesError::EntityNotFound(entity)) if entity == root
+        ));
+
+        let extracted = scene
+            .extract(
+                &selection,
+                &resources,
+                DanglingReferencePolicy::DropComponent,
+            )
+            .unwrap();
+        assert_eq!(extracted.entity_count(), 2);
+        let parents = extracted
+            .world
+            .query::<&SampleParent>()
+            .map(|parent| parent.0)
+            .collect::<Vec<_>>();
+        assert_eq!(parents.len(), 1);
+        assert_eq!(parent_x(&extracted, parents[0]), Some(1.0));
+
+        let extracted = scene
+            .extract(
+                &selection,
+                &resources,
+                DanglingReferencePolicy::KeepDangling,
+            )
+            .unwrap();
+        assert_eq!(extracted.entity_count(), 2);
+        let mut parents = extracted
+            .world
+            .query::<(&SamplePosition, &SampleParent)>()
+            .map(|(position, parent)| (position.x, parent_x(&extracted, parent.0)))
+            .collect::<Vec<_>>();
+        parents.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        // the child's parent wasn't extracted, so its reference dangles
+        assert_eq!(parents, vec![(1.0, None), (2.0, Some(1.0))]);
+    }
+
+    #[test]
+    fn require_components() {
+        let type_registry = sample_type_registry();
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+
+        let mut world = World::default();
+        world.spawn((
+            SamplePosition {
+                x: 1.0,
+                y: 2.0,
+                z: 0.0,
+            },
+            Transient,
+        ));
+        let missing = world.spawn((Transient,));
+        world.spawn((SamplePosition {
+            x: 3.0,
+            y: 4.0,
+            z: 0.0,
+        },));
+        let scene = Scene::new(world);
+
+        assert_eq!(
+            scene.require_components(&["SamplePosition"], &resources),
+            Err(vec![missing.id()])
+        );
+        assert_eq!(scene.require_components(&[], &resources), Ok(()));
+        assert_eq!(
+            scene
+                .require_components(&["SamplePosition", "Unregistered"], &resources)
+                .unwrap_err()
+                .len(),
+            3
+        );
+    }
 }
@@ -0,0 +1,188 @@
+use crate::{DynamicScene, DynamicSceneToWorldError, Entity, SceneEntityAllocator};
+use bevy_ecs::{Entity as WorldEntity, EntityBuilder, EntityMap, Resources, World};
+use bevy_type_registry::TypeRegistry;
+
+impl DynamicScene {
+    /// Writes the scene to `world`, handing each entity's loaded components to `assembler` before
+    /// the entity is spawned. The assembler receives the scene entity, to inspect which
+    /// components were saved, and a builder that already holds them, to which it can add derived
+    /// components. Returning an error rejects the entity and stops writing the scene with
+    /// [DynamicSceneToWorldError::Rejected]. On any error the entities spawned so far are
+    /// despawned again, so `world` is left as it was.
+    pub fn write_to_world_with_assembler(
+        &self,
+        world: &mut World,
+        resources: &Resources,
+        mut assembler: impl FnMut(&Entity, &mut EntityBuilder) -> Result<(), String>,
+    ) -> Result<(), DynamicSceneToWorldError> {
+        let mut spawned = Vec::with_capacity(self.entities.len());
+        let result = self.write_assembled(world, resources, &mut assembler, &mut spawned);
+        if result.is_err() {
+            for entity in spawned {
+                world.despawn(entity).unwrap();
+            }
+        }
+        result
+    }
+
+    fn write_assembled(
+        &self,
+        world: &mut World,
+        resources: &Resources,
+        assembler: &mut dyn FnMut(&Entity, &mut EntityBuilder) -> Result<(), String>,
+        spawned: &mut Vec<WorldEntity>,
+    ) -> Result<(), DynamicSceneToWorldError> {
+        let type_registry = resources.get::<TypeRegistry>().unwrap();
+        let component_registry = type_registry.component.read();
+        let mut allocator = SceneEntityAllocator::default();
+        let mut entity_map = EntityMap::default();
+        let mut builder = EntityBuilder::new();
+        for scene_entity in self.entities.iter() {
+            let mut registrations = Vec::with_capacity(scene_entity.components.len());
+            for component in scene_entity.components.iter() {
+                let registration = match component_registry.get_with_name(&component.type_name) {
+                    Some(registration) => registration,
+                    None => {
+                        builder.clear();
+                        return Err(DynamicSceneToWorldError::UnregisteredComponent {
+                            type_name: component.type_name.to_string(),
+                        });
+                    }
+                };
+                if let Err(reason) = registration.validate_property(component) {
+                    builder.clear();
+                    return Err(DynamicSceneToWorldError::InvalidComponent {
+                        type_name: component.type_name.to_string(),
+                        reason,
+                    });
+                }
+                registration.add_property_to_builder(&mut builder, resources, component);
+                registrations.push(registration);
+            }
+
+            if let Err(reason) = assembler(scene_entity, &mut builder) {
+                builder.clear();
+                return Err(DynamicSceneToWorldError::Rejected {
+                    entity: scene_entity.entity,
+                    reason,
+                });
+            }
+            let entity = world.spawn(builder.build());
+            spawned.push(entity);
+            entity_map.insert(allocator.register(scene_entity.entity), entity);
+            for registration in registrations {
+                registration.resolve_component(world, resources, entity);
+            }
+        }
+
+        for component_registration in component_registry.iter() {
+            component_registration.map_entities(world, &entity_map)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_support::{
+            register_sample_parent, sample_type_registry, SampleParent, SamplePosition,
+        },
+        DynamicScene, DynamicSceneToWorldError,
+    };
+    use bevy_ecs::{Resources, World};
+    use bevy_property::Properties;
+    use bevy_type_registry::TypeRegistry;
+
+    #[derive(Properties, Default)]
+    struct Health {
+        current: f32,
+        max: f32,
+    }
+
+    #[derive(Properties, Default)]
+    struct Name {
+        value: String,
+    }
+
+    struct Alive;
+
+    fn assemble_scene(world: &World, type_registry: &TypeRegistry) -> (World, Result<(), String>) {
+        let scene = DynamicScene::from_world_in_spawn_order(world, &type_registry.component.read());
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+        let mut loaded_world = World::default();
+        let result = scene
+            .write_to_world_with_assembler(&mut loaded_world, &resources, |entity, builder| {
+                let has_health = entity
+                    .components
+                    .iter()
+                    .any(|component| component.type_name.ends_with("Health"));
+                if !has_health {
+                    return Err("missing Health".to_string());
+                }
+                builder.add(Alive);
+                Ok(())
+            })
+            .map_err(|error| match error {
+                DynamicSceneToWorldError::Rejected { entity, reason } => {
+                    format!("{}: {}", entity, reason)
+                }
+                error => panic!("unexpected error {}", error),
+            });
+        (loaded_world, result)
+    }
+
+    #[test]
+    fn assembler() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().register::<Health>();
+        type_registry.component.write().register::<Name>();
+
+        let mut world = World::default();
+        world.spawn((
+            Health {
+                current: 5.0,
+                max: 10.0,
+            },
+            Name {
+                value: "hero".to_string(),
+            },
+        ));
+        let (loaded_world, result) = assemble_scene(&world, &type_registry);
+        assert_eq!(result, Ok(()));
+        let (health, _alive) = loaded_world.query::<(&Health, &Alive)>().next().unwrap();
+        assert_eq!((health.current, health.max), (5.0, 10.0));
+
+        let ghost = world.spawn((Name {
+            value: "ghost".to_string(),
+        },));
+        let (loaded_world, result) = assemble_scene(&world, &type_registry);
+        assert_eq!(result, Err(format!("{}: missing Health", ghost.id())));
+        // the hero spawned before the ghost was rejected is despawned again
+        assert_eq!(loaded_world.query::<&Name>().count(), 0);
+    }
+
+    #[test]
+    fn assembler_unmapped_entity() {
+        let type_registry = sample_type_registry();
+        register_sample_parent(&mut type_registry.component.write());
+        let mut world = World::default();
+        let outside = world.spawn(());
+        world.spawn((SamplePosition::default(), SampleParent(outside)));
+        world.despawn(outside).unwrap();
+
+        let scene = DynamicScene::from_world(&world, &type_registry.component.read());
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut loaded_world = World::default();
+        let result =
+            scene.write_to_world_with_assembler(&mut loaded_world, &resources, |_, _| Ok(()));
+        assert!(matches!(
+            result,
+            Err(DynamicSceneToWorldError::MapEntities(_))
+        ));
+        assert_eq!(loaded_world.query::<&SamplePosition>().count(), 0);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::DynamicScene;
+use bevy_ecs::World;
+use bevy_property::PropertyTypeRegistry;
+use bevy_type_registry::ComponentRegistry;
+use bevy_utils::HashSet;
+use std::any::TypeId;
+
+/// How much of a world is persisted when it's saved, to catch data that is silently dropped
+/// because its type isn't registered.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CoverageReport {
+    pub total_entities: usize,
+    /// Entities with at least one registered component.
+    pub serialized_entities: usize,
+    pub total_components: usize,
+    pub serialized_components: usize,
+    /// The component types that were found in the world but aren't registered.
+    pub unregistered_types: HashSet<TypeId>,
+}
+
+impl CoverageReport {
+    pub fn from_world(world: &World, component_registry: &ComponentRegistry) -> Self {
+        let mut report = CoverageReport::default();
+        for archetype in world.archetypes() {
+            let registered = archetype
+                .types()
+                .iter()
+                .filter(|type_info| {
+                    let is_registered = component_registry.get(&type_info.id()).is_some();
+                    if !is_registered {
+                        report.unregistered_types.insert(type_info.id());
+                    }
+                    is_registered
+                })
+                .count();
+            report.total_entities += archetype.len();
+            report.total_components += archetype.len() * archetype.types().len();
+            report.serialized_components += archetype.len() * registered;
+            if registered > 0 {
+                report.serialized_entities += archetype.len();
+            }
+        }
+        report
+    }
+}
+
+impl DynamicScene {
+    /// Serializes the registered components of `world` to RON like [DynamicScene::from_world]
+    /// followed by [DynamicScene::serialize_ron], and reports what was left out.
+    pub fn serialize_with_coverage(
+        world: &World,
+        component_registry: &ComponentRegistry,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<(String, CoverageReport), ron::Error> {
+        let ron = DynamicScene::from_world(world, component_registry).serialize_ron(registry)?;
+        Ok((ron, CoverageReport::from_world(world, component_registry)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_support::{register_sample_components, SamplePosition},
+        DynamicScene,
+    };
+    use bevy_ecs::World;
+    use bevy_property::PropertyTypeRegistry;
+    use bevy_type_registry::ComponentRegistry;
+    use std::any::TypeId;
+
+    struct Velocity;
+
+    struct Selected;
+
+    #[test]
+    fn serialize_with_coverage() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        world.spawn((
+            SamplePosition {
+                x: 3.0,
+                y: 4.0,
+                z: 0.0,
+            },
+            Velocity,
+        ));
+        world.spawn((Velocity, Selected));
+
+        let (ron, report) = DynamicScene::serialize_with_coverage(
+            &world,
+            &component_registry,
+            &PropertyTypeRegistry::default(),
+        )
+        .unwrap();
+        assert!(ron.contains("SamplePosition"));
+        assert_eq!(report.total_entities, 3);
+        assert_eq!(report.serialized_entities, 2);
+        assert_eq!(report.total_components, 5);
+        assert_eq!(report.serialized_components, 2);
+        assert_eq!(report.unregistered_types.len(), 2);
+        assert!(report
+            .unregistered_types
+            .contains(&TypeId::of::<Velocity>()));
+        assert!(report
+            .unregistered_types
+            .contains(&TypeId::of::<Selected>()));
+    }
+}
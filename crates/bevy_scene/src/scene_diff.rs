@@ -0,0 +1,661 @@
+use crate::{serialize_ron, DynamicScene, Entity};
+use bevy_property::{
+    property_serde::DynamicPropertiesSerializer, DynamicProperties, Properties, PropertiesVal,
+    Property, PropertyType, PropertyTypeRegistry,
+};
+use std::{collections::BTreeMap, fmt};
+
+/// The entities and components that differ between two [DynamicScene]s. Entities are matched by
+/// their scene id and components are compared by their serialized value.
+#[derive(Debug, Default)]
+pub struct SceneDiff {
+    pub added_entities: Vec<u32>,
+    pub removed_entities: Vec<u32>,
+    pub changed_components: Vec<ComponentDiff>,
+}
+
+#[derive(Debug)]
+pub struct ComponentDiff {
+    pub entity: u32,
+    pub type_name: String,
+    /// The serialized component before the change. `None` if the component was added.
+    pub before: Option<String>,
+    /// The serialized component after the change. `None` if the component was removed.
+    pub after: Option<String>,
+}
+
+type SerializedEntities = BTreeMap<u32, BTreeMap<String, String>>;
+
+/// Type name of the marker component that overrides store for a prefab component the instance
+/// removed. Its `type_name` field holds the removed component's type name.
+pub const REMOVED_COMPONENT: &str = "bevy_scene::RemovedComponent";
+/// Type name of the marker component that overrides store for a prefab entity the instance
+/// doesn't have.
+pub const REMOVED_ENTITY: &str = "bevy_scene::RemovedEntity";
+
+impl SceneDiff {
+    pub fn new(
+        before: &DynamicScene,
+        after: &DynamicScene,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<Self, ron::Error> {
+        let before_entities = serialize_entities(before, registry)?;
+        let after_entities = serialize_entities(after, registry)?;
+        let mut diff = SceneDiff::default();
+        for (entity, before_components) in before_entities.iter() {
+            let after_components = match after_entities.get(entity) {
+                Some(after_components) => after_components,
+                None => {
+                    diff.removed_entities.push(*entity);
+                    continue;
+                }
+            };
+
+            for (type_name, before_value) in before_components.iter() {
+                let after_value = after_components.get(type_name);
+                if after_value != Some(before_value) {
+                    diff.changed_components.push(ComponentDiff {
+                        entity: *entity,
+                        type_name: type_name.clone(),
+                        before: Some(before_value.clone()),
+                        after: after_value.cloned(),
+                    });
+                }
+            }
+
+            for (type_name, after_value) in after_components.iter() {
+                if !before_components.contains_key(type_name) {
+                    diff.changed_components.push(ComponentDiff {
+                        entity: *entity,
+                        type_name: type_name.clone(),
+                        before: None,
+                        after: Some(after_value.clone()),
+                    });
+                }
+            }
+        }
+
+        for entity in after_entities.keys() {
+            if !before_entities.contains_key(entity) {
+                diff.added_entities.push(*entity);
+            }
+        }
+
+        Ok(diff)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_entities.is_empty()
+            && self.removed_entities.is_empty()
+            && self.changed_components.is_empty()
+    }
+}
+
+impl fmt::Display for SceneDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entity in self.added_entities.iter() {
+            writeln!(f, "+ entity {}", entity)?;
+        }
+        for entity in self.removed_entities.iter() {
+            writeln!(f, "- entity {}", entity)?;
+        }
+        for component in self.changed_components.iter() {
+            writeln!(f, "~ entity {}: {}", component.entity, component.type_name)?;
+            if let Some(before) = &component.before {
+                writeln!(f, "  before: {}", before)?;
+            }
+            if let Some(after) = &component.after {
+                writeln!(f, "  after: {}", after)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns a human readable description of the differences between two scenes.
+pub fn scene_text_diff(
+    before: &DynamicScene,
+    after: &DynamicScene,
+    registry: &PropertyTypeRegistry,
+) -> Result<String, ron::Error> {
+    Ok(SceneDiff::new(before, after, registry)?.to_string())
+}
+
+impl DynamicScene {
+    /// Returns the components of this scene that differ from the prefab scene `base`, matching
+    /// entities by id. Entities that don't exist in `base` are kept whole and entities identical
+    /// to their template are left out. Components and entities of `base` missing from this scene
+    /// are recorded with [REMOVED_COMPONENT] and [REMOVED_ENTITY] markers. Resources are not part
+    /// of the overrides.
+    pub fn overrides(
+        &self,
+        base: &DynamicScene,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<DynamicScene, ron::Error> {
+        let base_entities = serialize_entities(base, registry)?;
+        let mut overrides = DynamicScene::default();
+        for entity in self.entities.iter() {
+            let template = base_entities.get(&entity.entity);
+            let mut components = Vec::new();
+            for component in entity.components.iter() {
+                if let Some(template) = template {
+                    let value =
+                        serialize_ron(DynamicPropertiesSerializer::new(component, registry))?;
+                    if template.get(component_type_name(component, registry)) == Some(&value) {
+                        continue;
+                    }
+                }
+                components.push(component.to_dynamic());
+            }
+            if let Some(template) = template {
+                let template_names = template.keys().map(String::as_str).collect::<Vec<_>>();
+                components.extend(removed_components(entity, &template_names, registry));
+            }
+
+            if !components.is_empty() {
+                overrides.entities.push(Entity {
+                    entity: entity.entity,
+                    components,
+                });
+            }
+        }
+        overrides
+            .entities
+            .extend(removed_entities(self, base_entities.keys().cloned()));
+
+        Ok(overrides)
+    }
+
+    /// Builds an instance of this prefab scene by starting from its entities and replacing or
+    /// adding the components stored in `overrides`, then removing the marked components and
+    /// entities.
+    pub fn apply_overrides(
+        &self,
+        overrides: &DynamicScene,
+        registry: &PropertyTypeRegistry,
+    ) -> DynamicScene {
+        let mut instance = DynamicScene::default();
+        for entity in self.entities.iter() {
+            instance.entities.push(Entity {
+                entity: entity.entity,
+                components: entity
+                    .components
+                    .iter()
+                    .map(|component| component.to_dynamic())
+                    .collect(),
+            });
+        }
+
+        for entity in overrides.entities.iter() {
+            let index = match instance
+                .entities
+                .iter()
+                .position(|instance_entity| instance_entity.entity == entity.entity)
+            {
+                Some(index) => index,
+                None => {
+                    instance.entities.push(Entity {
+                        entity: entity.entity,
+                        components: Vec::new(),
+                    });
+                    instance.entities.len() - 1
+                }
+            };
+            let components = &mut instance.entities[index].components;
+            for component in entity.components.iter() {
+                if is_removal(component) {
+                    continue;
+                }
+                let type_name = component_type_name(component, registry);
+                match components
+                    .iter()
+                    .position(|existing| component_type_name(existing, registry) == type_name)
+                {
+                    Some(existing) => components[existing] = component.to_dynamic(),
+                    None => components.push(component.to_dynamic()),
+                }
+            }
+        }
+        apply_removals(&mut instance, overrides, registry);
+
+        instance
+    }
+
+    /// Like [DynamicScene::overrides], but components that also exist in `base` only keep the
+    /// fields whose serialized value changed. Nested structs are compared field by field, while
+    /// sequences and value types are kept whole when they differ. Apply the result with
+    /// [DynamicScene::apply_field_overrides], or apply a single partial component to a live
+    /// entity with `ComponentRegistration::apply_property_to_entity`, which merges it into the
+    /// existing component.
+    pub fn field_overrides(
+        &self,
+        base: &DynamicScene,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<DynamicScene, ron::Error> {
+        let mut overrides = DynamicScene::default();
+        for entity in self.entities.iter() {
+            let template = base
+                .entities
+                .iter()
+                .find(|template| template.entity == entity.entity);
+            let mut components = Vec::new();
+            for component in entity.components.iter() {
+                let type_name = component_type_name(component, registry);
+                let template_component = template.and_then(|template| {
+                    template
+                        .components
+                        .iter()
+                        .find(|existing| component_type_name(existing, registry) == type_name)
+                });
+                match template_component {
+                    Some(template_component) => {
+                        if let Some(changed) =
+                            changed_fields(template_component, component, registry)?
+                        {
+                            components.push(changed);
+                        }
+                    }
+                    None => components.push(component.to_dynamic()),
+                }
+            }
+            if let Some(template) = template {
+                let template_names = template
+                    .components
+                    .iter()
+                    .map(|component| component_type_name(component, registry))
+                    .collect::<Vec<_>>();
+                components.extend(removed_components(entity, &template_names, registry));
+            }
+
+            if !components.is_empty() {
+                overrides.entities.push(Entity {
+                    entity: entity.entity,
+                    components,
+                });
+            }
+        }
+        let base_entities = base.entities.iter().map(|entity| entity.entity);
+        overrides
+            .entities
+            .extend(removed_entities(self, base_entities));
+
+        Ok(overrides)
+    }
+
+    /// Builds an instance of this scene with the partial components stored in `overrides`
+    /// deep-merged into its existing components. Components that don't exist yet are added.
+    pub fn apply_field_overrides(
+        &self,
+        overrides: &DynamicScene,
+        registry: &PropertyTypeRegistry,
+    ) -> DynamicScene {
+        // applying no overrides copies the scene
+        let mut instance = self.apply_overrides(&DynamicScene::default(), registry);
+        for entity in overrides.entities.iter() {
+            match instance
+                .entities
+                .iter_mut()
+                .find(|instance_entity| instance_entity.entity == entity.entity)
+            {
+                Some(instance_entity) => {
+                    for component in entity.components.iter().filter(|c| !is_removal(c)) {
+                        let type_name = component_type_name(component, registry);
+                        let components = &mut instance_entity.components;
+                        match components
+                            .iter_mut()
+                            .find(|existing| component_type_name(existing, registry) == type_name)
+                        {
+                            Some(existing) => existing.apply(component),
+                            None => components.push(component.to_dynamic()),
+                        }
+                    }
+                }
+                None => instance.entities.push(Entity {
+                    entity: entity.entity,
+                    components: entity
+                        .components
+                        .iter()
+                        .map(|component| component.to_dynamic())
+                        .collect(),
+                }),
+            }
+        }
+        apply_removals(&mut instance, overrides, registry);
+
+        instance
+    }
+}
+
+/// Returns a [REMOVED_COMPONENT] marker for each of the `template` type names that `entity`
+/// doesn't have.
+fn removed_components(
+    entity: &Entity,
+    template: &[&str],
+    registry: &PropertyTypeRegistry,
+) -> Vec<DynamicProperties> {
+    template
+        .iter()
+        .filter(|type_name| {
+            !entity
+                .components
+                .iter()
+                .any(|component| component_type_name(component, registry) == **type_name)
+        })
+        .map(|type_name| {
+            let mut marker = DynamicProperties::map();
+            marker.type_name = REMOVED_COMPONENT.to_string();
+            marker.set("type_name", type_name.to_string());
+            marker
+        })
+        .collect()
+}
+
+/// Returns an entity holding a [REMOVED_ENTITY] marker for each of the `base` entity ids that
+/// `scene` doesn't have.
+fn removed_entities(scene: &DynamicScene, base: impl Iterator<Item = u32>) -> Vec<Entity> {
+    base.filter(|id| !scene.entities.iter().any(|entity| entity.entity == *id))
+        .map(|id| {
+            let mut marker = DynamicProperties::map();
+            marker.type_name = REMOVED_ENTITY.to_string();
+            Entity {
+                entity: id,
+                components: vec![marker],
+            }
+        })
+        .collect()
+}
+
+fn is_removal(component: &DynamicProperties) -> bool {
+    component.type_name == REMOVED_COMPONENT || component.type_name == REMOVED_ENTITY
+}
+
+/// Removes the components and entities marked in `overrides` from `instance`.
+fn apply_removals(
+    instance: &mut DynamicScene,
+    overrides: &DynamicScene,
+    registry: &PropertyTypeRegistry,
+) {
+    for entity in overrides.entities.iter() {
+        for marker in entity.components.iter() {
+            if marker.type_name == REMOVED_ENTITY {
+                instance
+                    .entities
+                    .retain(|instance_entity| instance_entity.entity != entity.entity);
+            } else if marker.type_name == REMOVED_COMPONENT {
+                let removed = match marker.prop_val::<String>("type_name") {
+                    Some(removed) => removed,
+                    None => continue,
+                };
+                if let Some(instance_entity) = instance
+                    .entities
+                    .iter_mut()
+                    .find(|instance_entity| instance_entity.entity == entity.entity)
+                {
+                    instance_entity.components.retain(|component| {
+                        component_type_name(component, registry) != removed.as_str()
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Returns the fields of `after` that differ from `before`, or `None` if nothing changed.
+/// Sequences are compared as a whole.
+fn changed_fields(
+    before: &dyn Properties,
+    after: &dyn Properties,
+    registry: &PropertyTypeRegistry,
+) -> Result<Option<DynamicProperties>, ron::Error> {
+    if after.property_type() != PropertyType::Map || before.property_type() != PropertyType::Map {
+        return Ok(
+            if serialize_property(before, registry)? == serialize_property(after, registry)? {
+                None
+            } else {
+                Some(after.to_dynamic())
+            },
+        );
+    }
+
+    let mut changed = DynamicProperties::map();
+    changed.type_name = after.type_name().to_string();
+    for (index, after_field) in after.iter_props().enumerate() {
+        let name = after.prop_name(index).unwrap();
+        let before_field = match before.prop(name) {
+            Some(before_field) => before_field,
+            None => {
+                changed.set_box(name, after_field.clone_prop());
+                continue;
+            }
+        };
+        match (before_field.as_properties(), after_field.as_properties()) {
+            (Some(before_field), Some(after_field)) => {
+                if let Some(nested) = changed_fields(before_field, after_field, registry)? {
+                    changed.set(name, nested);
+                }
+            }
+            _ => {
+                if serialize_property(before_field, registry)?
+                    != serialize_property(after_field, registry)?
+                {
+                    changed.set_box(name, after_field.clone_prop());
+                }
+            }
+        }
+    }
+
+    Ok(if changed.prop_len() == 0 {
+        None
+    } else {
+        Some(changed)
+    })
+}
+
+fn serialize_property(
+    property: &dyn Property,
+    registry: &PropertyTypeRegistry,
+) -> Result<String, ron::Error> {
+    serialize_ron(property.serializable(registry).borrow())
+}
+
+fn serialize_entities(
+    scene: &DynamicScene,
+    registry: &PropertyTypeRegistry,
+) -> Result<SerializedEntities, ron::Error> {
+    let mut entities = SerializedEntities::new();
+    for entity in scene.entities.iter() {
+        let components = entities.entry(entity.entity).or_insert_with(BTreeMap::new);
+        for component in entity.components.iter() {
+            components.insert(
+                component_type_name(component, registry).to_string(),
+                serialize_ron(DynamicPropertiesSerializer::new(component, registry))?,
+            );
+        }
+    }
+
+    Ok(entities)
+}
+
+fn component_type_name<'a>(
+    component: &'a DynamicProperties,
+    registry: &'a PropertyTypeRegistry,
+) -> &'a str {
+    registry
+        .format_type_name(&component.type_name)
+        .unwrap_or(&component.type_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scene_text_diff, SceneDiff, REMOVED_COMPONENT, REMOVED_ENTITY};
+    use crate::{
+        test_support::{register_sample_components, sample_type_registry, SamplePosition},
+        DynamicScene,
+    };
+    use bevy_ecs::World;
+    use bevy_property::{Properties, PropertiesVal, PropertyTypeRegistry};
+    use bevy_type_registry::ComponentRegistry;
+
+    #[derive(Properties, Default)]
+    struct Health {
+        value: u32,
+    }
+
+    #[test]
+    fn text_diff() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let property_type_registry = PropertyTypeRegistry::default();
+
+        let mut world = World::default();
+        let entity = world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        let before = DynamicScene::from_world(&world, &component_registry);
+        world.get_mut::<SamplePosition>(entity).unwrap().x = 5.0;
+        let after = DynamicScene::from_world(&world, &component_registry);
+
+        let diff = SceneDiff::new(&before, &after, &property_type_registry).unwrap();
+        assert!(diff.added_entities.is_empty());
+        assert!(diff.removed_entities.is_empty());
+        assert_eq!(diff.changed_components.len(), 1);
+
+        let text = scene_text_diff(&before, &after, &property_type_registry).unwrap();
+        assert!(text.contains("SamplePosition"));
+        assert!(text.contains("5.0"));
+
+        let unchanged = SceneDiff::new(&after, &after, &property_type_registry).unwrap();
+        assert!(unchanged.is_empty());
+    }
+
+    #[test]
+    fn prefab_overrides() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        component_registry.register::<Health>();
+        let property_type_registry = PropertyTypeRegistry::default();
+
+        let mut world = World::default();
+        let entity = world.spawn((
+            SamplePosition {
+                x: 1.0,
+                y: 2.0,
+                z: 0.0,
+            },
+            Health { value: 10 },
+        ));
+        let prefab = DynamicScene::from_world(&world, &component_registry);
+        world.get_mut::<Health>(entity).unwrap().value = 4;
+        let instance = DynamicScene::from_world(&world, &component_registry);
+
+        let overrides = instance
+            .overrides(&prefab, &property_type_registry)
+            .unwrap();
+        assert_eq!(overrides.entities.len(), 1);
+        assert_eq!(overrides.entities[0].components.len(), 1);
+        let ron = overrides.serialize_ron(&property_type_registry).unwrap();
+        assert!(ron.contains("Health"));
+        assert!(!ron.contains("SamplePosition"));
+
+        let loaded = prefab.apply_overrides(&overrides, &property_type_registry);
+        assert!(SceneDiff::new(&instance, &loaded, &property_type_registry)
+            .unwrap()
+            .is_empty());
+        let health = loaded.entities[0]
+            .components
+            .iter()
+            .find(|component| component.type_name.ends_with("Health"))
+            .unwrap();
+        assert_eq!(*health.prop_val::<u32>("value").unwrap(), 4);
+    }
+
+    #[test]
+    fn removal_overrides() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        component_registry.register::<Health>();
+        let property_type_registry = PropertyTypeRegistry::default();
+
+        let mut world = World::default();
+        let entity = world.spawn((SamplePosition::default(), Health { value: 10 }));
+        let despawned = world.spawn((SamplePosition::default(),));
+        let prefab = DynamicScene::from_world(&world, &component_registry);
+        world.remove_one::<Health>(entity).unwrap();
+        world.despawn(despawned).unwrap();
+        let instance = DynamicScene::from_world(&world, &component_registry);
+
+        let overrides = instance
+            .overrides(&prefab, &property_type_registry)
+            .unwrap();
+        let ron = overrides.serialize_ron(&property_type_registry).unwrap();
+        assert!(ron.contains(REMOVED_COMPONENT));
+        assert!(ron.contains(REMOVED_ENTITY));
+        assert!(!ron.contains("SamplePosition\""));
+
+        let loaded = prefab.apply_overrides(&overrides, &property_type_registry);
+        assert_eq!(loaded.entities.len(), 1);
+        assert!(SceneDiff::new(&instance, &loaded, &property_type_registry)
+            .unwrap()
+            .is_empty());
+
+        let patch = instance
+            .field_overrides(&prefab, &property_type_registry)
+            .unwrap();
+        let patched = prefab.apply_field_overrides(&patch, &property_type_registry);
+        assert!(SceneDiff::new(&instance, &patched, &property_type_registry)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn field_overrides() {
+        let type_registry = sample_type_registry();
+        type_registry.component.write().register::<Health>();
+        let property_type_registry = type_registry.property.read();
+
+        let mut world = World::default();
+        let entity = world.spawn((
+            SamplePosition {
+                x: 1.0,
+                y: 2.0,
+                z: 0.0,
+            },
+            Health { value: 10 },
+        ));
+        let before = DynamicScene::from_world(&world, &type_registry.component.read());
+        world.get_mut::<SamplePosition>(entity).unwrap().x = 5.0;
+        let after = DynamicScene::from_world(&world, &type_registry.component.read());
+
+        let patch = after
+            .field_overrides(&before, &property_type_registry)
+            .unwrap();
+        assert_eq!(patch.entities.len(), 1);
+        let position = &patch.entities[0].components;
+        assert_eq!(position.len(), 1);
+        assert_eq!(position[0].prop_len(), 1);
+        assert_eq!(*position[0].prop_val::<f32>("x").unwrap(), 5.0);
+
+        let patched = before.apply_field_overrides(&patch, &property_type_registry);
+        assert!(SceneDiff::new(&after, &patched, &property_type_registry)
+            .unwrap()
+            .is_empty());
+
+        // a partial component merges into a live entity as well
+        let mut live_world = World::default();
+        let live_entity = live_world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        type_registry
+            .component
+            .read()
+            .get_with_name("SamplePosition")
+            .unwrap()
+            .apply_property_to_entity(&mut live_world, live_entity, &position[0]);
+        let live_position = live_world.get::<SamplePosition>(live_entity).unwrap();
+        assert_eq!((live_position.x, live_position.y), (5.0, 2.0));
+    }
+}
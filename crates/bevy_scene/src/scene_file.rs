@@ -0,0 +1,455 @@
+use crate::{
+    serde::{SceneDeserializer, SceneSerializer},
+    serialize_ron_to_writer, DynamicScene,
+};
+use bevy_property::PropertyTypeRegistry;
+use serde::de::DeserializeSeed;
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use thiserror::Error;
+
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Error, Debug)]
+pub enum SceneError {
+    #[error("Encountered an io error while accessing the scene file.")]
+    Io(#[from] io::Error),
+    #[error("Failed to serialize or parse the scene.")]
+    Ron(#[from] ron::Error),
+    #[error("Failed to serialize or parse the scene as JSON.")]
+    Json(#[from] serde_json::Error),
+    #[error("Scene checksum mismatch: expected {expected:08x}, found {found:08x}.")]
+    ChecksumMismatch { expected: u32, found: u32 },
+    #[error("Scene file has no checksum line.")]
+    MissingChecksum,
+    #[error("Entity {0} doesn't exist.")]
+    NoSuchEntity(u32),
+    #[error("Scene serialization was cancelled.")]
+    Cancelled,
+    #[error("Grid cell size must be positive and finite, got {0}.")]
+    InvalidCellSize(f32),
+    #[error("Component {type_name} can't be serialized: {reason}")]
+    UnserializableComponent { type_name: String, reason: String },
+}
+
+/// Controls how scene files are written and read back.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SceneFileSettings {
+    /// Appends a `// crc32: xxxxxxxx` comment line holding the CRC32 of the payload when saving
+    /// and verifies it when loading. The file stays readable RON text.
+    pub checksum: bool,
+}
+
+const CHECKSUM_PREFIX: &str = "// crc32: ";
+
+impl DynamicScene {
+    pub fn save_to_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<(), SceneError> {
+        self.save_to_path_with_settings(path, registry, SceneFileSettings::default())
+    }
+
+    pub fn save_to_path_with_settings<P: AsRef<Path>>(
+        &self,
+        path: P,
+        registry: &PropertyTypeRegistry,
+        settings: SceneFileSettings,
+    ) -> Result<(), SceneError> {
+        let mut bytes = self.serialize_ron(registry)?.into_bytes();
+        if settings.checksum {
+            let checksum = crc32fast::hash(&bytes);
+            bytes.extend_from_slice(format!("\n{}{:08x}\n", CHECKSUM_PREFIX, checksum).as_bytes());
+        }
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// Like [DynamicScene::save_to_path], but stops with [SceneError::Cancelled] once `cancel` is
+    /// set, e.g. by an editor's cancel button on another thread. Nothing is written to `path`
+    /// when the save is cancelled.
+    pub fn save_to_path_with_cancel_flag<P: AsRef<Path>>(
+        &self,
+        path: P,
+        registry: &PropertyTypeRegistry,
+        cancel: &AtomicBool,
+    ) -> Result<(), SceneError> {
+        let ron = SceneSerializer::new(self, registry)
+            .with_cancel_flag(cancel)
+            .serialize_ron()?;
+        fs::write(path, ron)?;
+        Ok(())
+    }
+
+    /// Writes the scene to a temporary file next to `path` and then renames it over `path`, so an
+    /// interrupted or failed save never leaves a partially written file in place. The temporary
+    /// file name is unique to the save, so concurrent saves to the same path don't collide.
+    pub fn save_to_path_atomic<P: AsRef<Path>>(
+        &self,
+        path: P,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<(), SceneError> {
+        let path = path.as_ref();
+        let ron = self.serialize_ron(registry)?;
+        let temp_path = temp_path_for(path, TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed));
+        let result =
+            write_synced(&temp_path, ron.as_bytes()).and_then(|_| fs::rename(&temp_path, path));
+        if let Err(err) = result {
+            let _ = fs::remove_file(&temp_path);
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    /// The number of bytes [DynamicScene::serialize_into_slice] writes, computed without
+    /// buffering the output.
+    pub fn serialized_len(&self, registry: &PropertyTypeRegistry) -> Result<usize, SceneError> {
+        let mut counter = ByteCounter(0);
+        serialize_ron_to_writer(&mut counter, SceneSerializer::new(self, registry))?;
+        Ok(counter.0)
+    }
+
+    /// Writes the scene's RON into `buffer`, e.g. a memory-mapped file sized with
+    /// [DynamicScene::serialized_len], and returns the number of bytes written. Fails if `buffer`
+    /// is too small.
+    pub fn serialize_into_slice(
+        &self,
+        buffer: &mut [u8],
+        registry: &PropertyTypeRegistry,
+    ) -> Result<usize, SceneError> {
+        let len = buffer.len();
+        let mut remaining = buffer;
+        serialize_ron_to_writer(&mut remaining, SceneSerializer::new(self, registry))?;
+        Ok(len - remaining.len())
+    }
+
+    /// Parses a scene from RON bytes, such as a memory-mapped file, without copying them.
+    pub fn deserialize_from_slice(
+        bytes: &[u8],
+        registry: &PropertyTypeRegistry,
+    ) -> Result<DynamicScene, SceneError> {
+        let mut deserializer = ron::de::Deserializer::from_bytes(bytes)?;
+        let scene = SceneDeserializer {
+            property_type_registry: registry,
+        }
+        .deserialize(&mut deserializer)?;
+        Ok(scene)
+    }
+
+    pub fn load_from_path<P: AsRef<Path>>(
+        path: P,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<DynamicScene, SceneError> {
+        Self::load_from_path_with_settings(path, registry, SceneFileSettings::default())
+    }
+
+    pub fn load_from_path_with_settings<P: AsRef<Path>>(
+        path: P,
+        registry: &PropertyTypeRegistry,
+        settings: SceneFileSettings,
+    ) -> Result<DynamicScene, SceneError> {
+        let bytes = fs::read(path)?;
+        let payload = if settings.checksum {
+            verify_checksum(&bytes)?
+        } else {
+            &bytes[..]
+        };
+        Self::deserialize_from_slice(payload, registry)
+    }
+}
+
+fn verify_checksum(bytes: &[u8]) -> Result<&[u8], SceneError> {
+    let text = if bytes.ends_with(b"\n") {
+        &bytes[..bytes.len() - 1]
+    } else {
+        bytes
+    };
+    // the trailer is the last line, separated from the payload by a newline
+    let separator = text
+        .iter()
+        .rposition(|byte| *byte == b'\n')
+        .ok_or(SceneError::MissingChecksum)?;
+    let (payload, trailer) = (&text[..separator], &text[separator + 1..]);
+    let expected = std::str::from_utf8(trailer)
+        .ok()
+        .and_then(|trailer| trailer.strip_prefix(CHECKSUM_PREFIX))
+        .filter(|hex| hex.len() == 8 && hex.bytes().all(|byte| byte.is_ascii_hexdigit()))
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .ok_or(SceneError::MissingChecksum)?;
+    let found = crc32fast::hash(payload);
+    if expected != found {
+        return Err(SceneError::ChecksumMismatch { expected, found });
+    }
+    Ok(payload)
+}
+
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `<file name>.<process id>.<counter>.tmp` next to `path`.
+fn temp_path_for(path: &Path, counter: usize) -> PathBuf {
+    let mut file_name = path
+        .file_name()
+        .map(|file_name| file_name.to_os_string())
+        .unwrap_or_else(|| OsString::from("scene"));
+    file_name.push(format!(".{}.{}.tmp", std::process::id(), counter));
+    path.with_file_name(file_name)
+}
+
+fn write_synced(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{temp_path_for, SceneError, SceneFileSettings, TEMP_FILE_COUNTER};
+    use crate::{
+        test_support::{register_sample_components, SamplePosition},
+        DynamicScene,
+    };
+    use bevy_ecs::World;
+    use bevy_property::PropertyTypeRegistry;
+    use bevy_type_registry::ComponentRegistry;
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("bevy_scene_{}_{}", std::process::id(), name))
+    }
+
+    fn leftover_temp_files(path: &Path) -> usize {
+        let prefix = format!("{}.", path.file_name().unwrap().to_str().unwrap());
+        fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .filter_map(|entry| entry.unwrap().file_name().into_string().ok())
+            .filter(|name| name.starts_with(&prefix) && name.ends_with(".tmp"))
+            .count()
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let registry = PropertyTypeRegistry::default();
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        let scene = DynamicScene::from_world(&world, &component_registry);
+
+        let path = temp_path("save_and_load.scn");
+        scene.save_to_path(&path, &registry).unwrap();
+        let loaded = DynamicScene::load_from_path(&path, &registry).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.entities.len(), 1);
+        assert_eq!(
+            loaded.serialize_ron(&registry).unwrap(),
+            scene.serialize_ron(&registry).unwrap()
+        );
+    }
+
+    #[test]
+    fn cancelled_save_writes_nothing() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let registry = PropertyTypeRegistry::default();
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        let scene = DynamicScene::from_world(&world, &component_registry);
+
+        let path = temp_path("cancelled.scn");
+        let cancel = AtomicBool::new(true);
+        let result = scene.save_to_path_with_cancel_flag(&path, &registry, &cancel);
+        assert!(matches!(result, Err(SceneError::Cancelled)));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn failed_atomic_save_keeps_original() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let registry = PropertyTypeRegistry::default();
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        let scene = DynamicScene::from_world(&world, &component_registry);
+
+        let path = temp_path("atomic.scn");
+        scene.save_to_path_atomic(&path, &registry).unwrap();
+        let original = fs::read_to_string(&path).unwrap();
+        assert_eq!(leftover_temp_files(&path), 0);
+
+        // a directory in place of the temporary file makes the next write fail
+        world.spawn((SamplePosition {
+            x: 3.0,
+            y: 4.0,
+            z: 0.0,
+        },));
+        let scene = DynamicScene::from_world(&world, &component_registry);
+        let next_temp_path = temp_path_for(&path, TEMP_FILE_COUNTER.load(Ordering::Relaxed));
+        fs::create_dir(&next_temp_path).unwrap();
+        let error = scene.save_to_path_atomic(&path, &registry).unwrap_err();
+        fs::remove_dir(&next_temp_path).unwrap();
+        assert!(matches!(error, SceneError::Io(_)));
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        fs::remove_file(&path).unwrap();
+
+        // a directory in place of the scene file makes the rename fail
+        let path = temp_path("atomic_dir.scn");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("keep"), "").unwrap();
+        let error = scene.save_to_path_atomic(&path, &registry).unwrap_err();
+        assert!(matches!(error, SceneError::Io(_)));
+        assert_eq!(leftover_temp_files(&path), 0);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_file_is_io_error() {
+        let path = temp_path("missing.scn");
+        let error =
+            DynamicScene::load_from_path(&path, &PropertyTypeRegistry::default()).unwrap_err();
+        assert!(matches!(error, SceneError::Io(_)));
+    }
+
+    #[test]
+    fn corrupt_file_is_parse_error() {
+        let path = temp_path("corrupt.scn");
+        fs::write(&path, "[(entity: 0, components: [").unwrap();
+        let error =
+            DynamicScene::load_from_path(&path, &PropertyTypeRegistry::default()).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(error, SceneError::Ron(_)));
+    }
+
+    #[test]
+    fn checksum_detects_corruption() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let registry = PropertyTypeRegistry::default();
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        let scene = DynamicScene::from_world(&world, &component_registry);
+        let settings = SceneFileSettings { checksum: true };
+
+        let path = temp_path("checksum.scn");
+        scene
+            .save_to_path_with_settings(&path, &registry, settings)
+            .unwrap();
+        let loaded =
+            DynamicScene::load_from_path_with_settings(&path, &registry, settings).unwrap();
+        assert_eq!(loaded.entities.len(), 1);
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[1] ^= 0x01;
+        fs::write(&path, bytes).unwrap();
+        let error =
+            DynamicScene::load_from_path_with_settings(&path, &registry, settings).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(error, SceneError::ChecksumMismatch { .. }));
+    }
+
+    #[test]
+    fn checksum_is_a_text_trailer() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let registry = PropertyTypeRegistry::default();
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        let scene = DynamicScene::from_world(&world, &component_registry);
+        let settings = SceneFileSettings { checksum: true };
+
+        let path = temp_path("checksum_text.scn");
+        scene
+            .save_to_path_with_settings(&path, &registry, settings)
+            .unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let trailer = text.lines().last().unwrap();
+        assert!(trailer.starts_with("// crc32: "));
+        // the trailer is a RON comment, so the file also loads without verifying it
+        assert_eq!(
+            DynamicScene::load_from_path(&path, &registry)
+                .unwrap()
+                .entities
+                .len(),
+            1
+        );
+
+        fs::write(&path, scene.serialize_ron(&registry).unwrap()).unwrap();
+        let error =
+            DynamicScene::load_from_path_with_settings(&path, &registry, settings).unwrap_err();
+        assert!(matches!(error, SceneError::MissingChecksum));
+        fs::write(&path, "ab").unwrap();
+        let error =
+            DynamicScene::load_from_path_with_settings(&path, &registry, settings).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(error, SceneError::MissingChecksum));
+    }
+
+    #[test]
+    fn serialize_into_slice() {
+        let mut component_registry = ComponentRegistry::default();
+        register_sample_components(&mut component_registry);
+        let registry = PropertyTypeRegistry::default();
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        let scene = DynamicScene::from_world(&world, &component_registry);
+
+        let len = scene.serialized_len(&registry).unwrap();
+        let mut buffer = vec![0; len];
+        assert_eq!(
+            scene.serialize_into_slice(&mut buffer, &registry).unwrap(),
+            len
+        );
+        assert_eq!(buffer, scene.serialize_ron(&registry).unwrap().into_bytes());
+        let loaded = DynamicScene::deserialize_from_slice(&buffer, &registry).unwrap();
+        assert_eq!(loaded.entities.len(), 1);
+
+        let error = scene
+            .serialize_into_slice(&mut buffer[..len - 1], &registry)
+            .unwrap_err();
+        assert!(matches!(error, SceneError::Ron(_) | SceneError::Io(_)));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{DynamicScene, SceneError};
+use bevy_ecs::{Entity as WorldEntity, World};
+use bevy_property::PropertyTypeRegistry;
+use bevy_type_registry::ComponentRegistry;
+use bevy_utils::HashMap;
+use std::collections::BTreeMap;
+
+/// The coordinates of a square cell of a [serialize_by_grid] grid.
+pub type GridCell = (i32, i32);
+
+/// Splits the registered components of `world` into one RON scene per grid cell so an open world
+/// can be streamed in cell by cell. `position_fn` returns the 2D position an entity is bucketed
+/// by; entities it returns `None` for aren't saved. Cell `(0, 0)` covers positions from `0.0` up
+/// to, but excluding, `cell_size` on both axes, which must be positive and finite. Load a cell
+/// with [DynamicScene::deserialize_from_slice] and write it to a world to spawn just its entities.
+pub fn serialize_by_grid(
+    world: &World,
+    component_registry: &ComponentRegistry,
+    registry: &PropertyTypeRegistry,
+    cell_size: f32,
+    position_fn: impl Fn(&World, WorldEntity) -> Option<(f32, f32)>,
+) -> Result<BTreeMap<GridCell, Vec<u8>>, SceneError> {
+    if !(cell_size.is_finite() && cell_size > 0.0) {
+        return Err(SceneError::InvalidCellSize(cell_size));
+    }
+    let handles = world
+        .query::<WorldEntity>()
+        .map(|entity| (entity.id(), entity))
+        .collect::<HashMap<_, _>>();
+    let mut cells = BTreeMap::<GridCell, DynamicScene>::new();
+    for entity in DynamicScene::from_world(world, component_registry).entities {
+        let (x, y) = match position_fn(world, handles[&entity.entity]) {
+            Some(position) => position,
+            None => continue,
+        };
+        let cell = (
+            (x / cell_size).floor() as i32,
+            (y / cell_size).floor() as i32,
+        );
+        cells.entry(cell).or_default().entities.push(entity);
+    }
+
+    let mut serialized = BTreeMap::new();
+    for (cell, scene) in cells {
+        serialized.insert(cell, scene.serialize_ron(registry)?.into_bytes());
+    }
+    Ok(serialized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::serialize_by_grid;
+    use crate::{
+        test_support::{build_sample_world, sample_type_registry, SampleName, SamplePosition},
+        DynamicScene, SceneError,
+    };
+    use bevy_ecs::{Resources, World};
+    use bevy_property::PropertyTypeRegistry;
+
+    #[test]
+    fn serialize_by_grid_cells() {
+        let type_registry = sample_type_registry();
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 1.0,
+            z: 0.0,
+        },));
+        world.spawn((SamplePosition {
+            x: 9.5,
+            y: 0.0,
+            z: 0.0,
+        },));
+        world.spawn((SamplePosition {
+            x: -3.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        world.spawn((SampleName {
+            value: "unplaced".to_string(),
+        },));
+
+        let cells = serialize_by_grid(
+            &world,
+            &type_registry.component.read(),
+            &type_registry.property.read(),
+            10.0,
+            |world, entity| {
+                let position = world.get::<SamplePosition>(entity).ok()?;
+                Some((position.x, position.y))
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            cells.keys().cloned().collect::<Vec<_>>(),
+            vec![(-1, 0), (0, 0)]
+        );
+
+        let scene =
+            DynamicScene::deserialize_from_slice(&cells[&(0, 0)], &type_registry.property.read())
+                .unwrap();
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut loaded_world = World::default();
+        scene.write_to_world(&mut loaded_world, &resources).unwrap();
+        let mut xs = loaded_world
+            .query::<&SamplePosition>()
+            .map(|position| position.x)
+            .collect::<Vec<_>>();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(xs, vec![1.0, 9.5]);
+        assert_eq!(loaded_world.query::<&SampleName>().count(), 0);
+    }
+
+    #[test]
+    fn invalid_cell_size() {
+        let (world, component_registry) = build_sample_world(1, 4);
+        let registry = PropertyTypeRegistry::default();
+        for &cell_size in [0.0, -1.0, f32::NAN, f32::INFINITY].iter() {
+            let result =
+                serialize_by_grid(&world, &component_registry, &registry, cell_size, |_, _| {
+                    Some((0.0, 0.0))
+                });
+            assert!(matches!(result, Err(SceneError::InvalidCellSize(_))));
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{DynamicScene, DynamicSceneToWorldError, SceneEntityAllocator};
+use bevy_ecs::{EntityBuilder, EntityMap, Resources, World};
+use bevy_type_registry::{ComponentRegistry, TypeRegistry};
+use serde::{Deserialize, Serialize};
+
+/// Advisory information about the world a [DynamicScene] was taken from, stored in the scene's
+/// `metadata` field.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneMetadata {
+    /// The number of saved entities. [DynamicScene::write_to_world_in_layout] reserves this many
+    /// entity ids before spawning.
+    pub entity_count: usize,
+    /// The registered component type names of each non-empty archetype, in the world's archetype
+    /// order.
+    pub archetypes: Vec<Vec<String>>,
+}
+
+impl SceneMetadata {
+    pub fn from_world(world: &World, component_registry: &ComponentRegistry) -> Self {
+        let mut metadata = SceneMetadata::default();
+        for archetype in world.archetypes().filter(|archetype| !archetype.is_empty()) {
+            let type_names = archetype
+                .types()
+                .iter()
+                .filter_map(|type_info| component_registry.get(&type_info.id()))
+                .map(|registration| registration.long_name.to_string())
+                .collect::<Vec<_>>();
+            if type_names.is_empty() {
+                continue;
+            }
+            metadata.entity_count += archetype.len();
+            if !metadata.archetypes.contains(&type_names) {
+                metadata.archetypes.push(type_names);
+            }
+        }
+        metadata
+    }
+}
+
+impl DynamicScene {
+    /// Like [DynamicScene::from_world], but also records the world's [SceneMetadata].
+    pub fn from_world_with_metadata(world: &World, component_registry: &ComponentRegistry) -> Self {
+        let mut scene = Self::from_world(world, component_registry);
+        scene.metadata = Some(SceneMetadata::from_world(world, component_registry));
+        scene
+    }
+
+    /// Writes the scene so the world ends up with the archetype order recorded in its
+    /// [SceneMetadata]: entities are written grouped by archetype, and each one is spawned with
+    /// all of its components at once so no intermediate archetypes are created. Without metadata
+    /// the entities are written in scene order.
+    pub fn write_to_world_in_layout(
+        &self,
+        world: &mut World,
+        resources: &Resources,
+    ) -> Result<(), DynamicSceneToWorldError> {
+        let type_registry = resources.get::<TypeRegistry>().unwrap();
+        let component_registry = type_registry.component.read();
+        let archetypes = self
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.archetypes.as_slice())
+            .unwrap_or_default();
+        let mut allocator = SceneEntityAllocator::default();
+        let mut entities = self
+            .entities
+            .iter()
+            .map(|entity| (allocator.register(entity.entity), entity))
+            .collect::<Vec<_>>();
+        // entities that match no recorded archetype go last, keeping their scene order
+        entities.sort_by_key(|(_, entity)| {
+            archetypes
+                .iter()
+                .position(|type_names| {
+                    type_names.len() == entity.components.len()
+                        && entity
+                            .components
+                            .iter()
+                            .all(|component| type_names.contains(&component.type_name))
+                })
+                .unwrap_or_else(|| archetypes.len())
+        });
+
+        if let Some(metadata) = self.metadata.as_ref() {
+            // never trust the hint beyond what the scene actually holds
+            let capacity = metadata.entity_count.min(self.entities.len());
+            world.reserve::<()>(capacity as u32);
+        }
+
+        let mut entity_map = EntityMap::default();
+        let mut builder = EntityBuilder::new();
+        for (scene_entity_id, scene_entity) in entities {
+            let mut registrations = Vec::with_capacity(scene_entity.components.len());
+            for component in scene_entity.components.iter() {
+                let registration = component_registry
+                    .get_with_name(&component.type_name)
+                    .ok_or_else(|| DynamicSceneToWorldError::UnregisteredComponent {
+                        type_name: component.type_name.to_string(),
+                    })?;
+                registration
+                    .validate_property(component)
+                    .map_err(|reason| DynamicSceneToWorldError::InvalidComponent {
+                        type_name: component.type_name.to_string(),
+                        reason,
+                    })?;
+                registration.add_property_to_builder(&mut builder, resources, component);
+                registrations.push(registration);
+            }
+
+            let entity = world.spawn(builder.build());
+            entity_map.insert(scene_entity_id, entity);
+            for registration in registrations {
+                registration.resolve_component(world, resources, entity);
+            }
+        }
+
+        for component_registration in component_registry.iter() {
+            component_registration.map_entities(world, &entity_map)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        serde::SceneDeserializer,
+        test_support::{
+            register_sample_parent, sample_type_registry, SampleParent, SamplePosition,
+            SampleVelocity,
+        },
+        DynamicScene, DynamicSceneToWorldError, SceneMetadata,
+    };
+    use bevy_ecs::{Entity, Resources, World};
+    use serde::de::DeserializeSeed;
+    use std::any::TypeId;
+
+    fn archetype_layout(world: &World) -> Vec<Vec<TypeId>> {
+        world
+            .archetypes()
+            .filter(|archetype| !archetype.is_empty())
+            .map(|archetype| archetype.types().iter().map(|ty| ty.id()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn metadata_round_trip() {
+        let type_registry = sample_type_registry();
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+
+        // the first entity moves to a newer archetype, so spawn order and archetype order differ
+        let mut world = World::default();
+        let first = world.spawn((SamplePosition {
+            x: 1.0,
+            y: 1.0,
+            z: 0.0,
+        },));
+        world.spawn((
+            SamplePosition {
+                x: 2.0,
+                y: 2.0,
+                z: 0.0,
+            },
+            SampleVelocity {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            },
+        ));
+        world.spawn((SamplePosition {
+            x: 3.0,
+            y: 3.0,
+            z: 0.0,
+        },));
+        world
+            .insert_one(
+                first,
+                SampleVelocity {
+                    x: 1.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+            )
+            .unwrap();
+
+        let scene =
+            DynamicScene::from_world_in_spawn_order(&world, &type_registry.component.read());
+        let metadata = SceneMetadata::from_world(&world, &type_registry.component.read());
+        assert_eq!(metadata.entity_count, 3);
+        assert_eq!(metadata.archetypes.len(), 2);
+        let scene = DynamicScene {
+            metadata: Some(metadata.clone()),
+            ..scene
+        };
+
+        let ron = scene.serialize_ron(&type_registry.property.read()).unwrap();
+        let property_type_registry = type_registry.property.read();
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let loaded_scene = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+        assert_eq!(loaded_scene.metadata, Some(metadata));
+
+        let mut loaded_world = World::default();
+        loaded_scene
+            .write_to_world_in_layout(&mut loaded_world, &resources)
+            .unwrap();
+        assert_eq!(archetype_layout(&loaded_world), archetype_layout(&world));
+
+        // loaders that ignore the metadata still load every entity
+        let mut plain_world = World::default();
+        loaded_scene
+            .write_to_world(&mut plain_world, &resources)
+            .unwrap();
+        assert_eq!(plain_world.query::<&SamplePosition>().count(), 3);
+    }
+
+    #[test]
+    fn layout_dangling_reference() {
+        let type_registry = sample_type_registry();
+        register_sample_parent(&mut type_registry.component.write());
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+
+        let mut world = World::default();
+        world.spawn((SamplePosition::default(), SampleParent(Entity::new(42))));
+        let scene = DynamicScene::from_world_with_metadata(&world, &type_registry.component.read());
+
+        let mut loaded_world = World::default();
+        let result = scene.write_to_world_in_layout(&mut loaded_world, &resources);
+        assert!(matches!(
+            result,
+            Err(DynamicSceneToWorldError::MapEntities(_))
+        ));
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{DynamicScene, SceneError};
+use bevy_property::PropertyTypeRegistry;
+use memmap2::{Mmap, MmapMut};
+use std::{
+    fs::{File, OpenOptions},
+    path::Path,
+};
+
+impl DynamicScene {
+    /// Saves the scene by serializing it straight into a memory-mapped file, so the RON is never
+    /// buffered in memory as a whole.
+    pub fn save_to_path_mmap<P: AsRef<Path>>(
+        &self,
+        path: P,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<(), SceneError> {
+        let len = self.serialized_len(registry)?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(len as u64)?;
+        if len == 0 {
+            return Ok(());
+        }
+        // SAFETY: the file was just truncated and sized here and is only written through this
+        // mapping, which is dropped before returning
+        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+        self.serialize_into_slice(&mut mmap, registry)?;
+        mmap.flush()?;
+        Ok(())
+    }
+
+    /// Loads a scene by parsing a memory-mapped view of the file instead of reading it into a
+    /// buffer first.
+    pub fn load_from_path_mmap<P: AsRef<Path>>(
+        path: P,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<DynamicScene, SceneError> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is only read for the duration of this call; the file must not be
+        // truncated by another process while the scene is parsed
+        let mmap = unsafe { Mmap::map(&file)? };
+        DynamicScene::deserialize_from_slice(&mmap, registry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_support::build_sample_scene, DynamicScene};
+    use bevy_property::PropertyTypeRegistry;
+    use std::fs;
+
+    #[test]
+    fn mmap_round_trip() {
+        let registry = PropertyTypeRegistry::default();
+        let scene = build_sample_scene(3, 50);
+        let path = std::env::temp_dir().join(format!("bevy_scene_{}_mmap.scn", std::process::id()));
+
+        scene.save_to_path_mmap(&path, &registry).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            scene.serialize_ron(&registry).unwrap()
+        );
+        let loaded = DynamicScene::load_from_path_mmap(&path, &registry).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded.content_hash(&registry).unwrap(),
+            scene.content_hash(&registry).unwrap()
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    serde::{
+        ComponentVecDeserializer, ComponentsSerializer, EntitySerializer, SceneEntityDeserializer,
+    },
+    DynamicScene, SceneError,
+};
+use bevy_property::PropertyTypeRegistry;
+use serde::{
+    de::{DeserializeSeed, Error, MapAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Serialize,
+};
+use std::io::{BufRead, Write};
+
+/// The version written to the header line of NDJSON scenes.
+pub const NDJSON_VERSION: u32 = 1;
+
+const HEADER_STRUCT: &str = "Header";
+const HEADER_FIELD_VERSION: &str = "version";
+const HEADER_FIELD_RESOURCES: &str = "resources";
+
+impl DynamicScene {
+    /// Writes the scene as newline-delimited JSON: a header line holding the format version and
+    /// the resources, followed by one line per entity.
+    pub fn to_ndjson_writer<W: Write>(
+        &self,
+        mut writer: W,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<(), SceneError> {
+        serde_json::to_writer(
+            &mut writer,
+            &HeaderSerializer {
+                scene: self,
+                registry,
+            },
+        )?;
+        writer.write_all(b"\n")?;
+        for entity in self.entities.iter() {
+            serde_json::to_writer(&mut writer, &EntitySerializer { entity, registry })?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Reads a scene written by [DynamicScene::to_ndjson_writer], parsing one line at a time.
+    /// Blank lines are skipped.
+    pub fn from_ndjson_reader<R: BufRead>(
+        reader: R,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<DynamicScene, SceneError> {
+        let mut lines = reader.lines().filter(|line| match line {
+            Ok(line) => !line.trim().is_empty(),
+            Err(_) => true,
+        });
+        let header = lines
+            .next()
+            .ok_or_else(|| serde_json::Error::custom("missing NDJSON scene header"))??;
+        let mut scene = HeaderDeserializer { registry }
+            .deserialize(&mut serde_json::Deserializer::from_str(&header))?;
+        for line in lines {
+            let entity = SceneEntityDeserializer {
+                property_type_registry: registry,
+            }
+            .deserialize(&mut serde_json::Deserializer::from_str(&line?))?;
+            scene.entities.push(entity);
+        }
+        Ok(scene)
+    }
+}
+
+struct HeaderSerializer<'a> {
+    scene: &'a DynamicScene,
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a> Serialize for HeaderSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct(HEADER_STRUCT, 2)?;
+        state.serialize_field(HEADER_FIELD_VERSION, &NDJSON_VERSION)?;
+        state.serialize_field(
+            HEADER_FIELD_RESOURCES,
+            &ComponentsSerializer {
+                components: &self.scene.resources,
+                registry: self.registry,
+            },
+        )?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum HeaderField {
+    Version,
+    Resources,
+}
+
+struct HeaderDeserializer<'a> {
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for HeaderDeserializer<'a> {
+    type Value = DynamicScene;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            HEADER_STRUCT,
+            &[HEADER_FIELD_VERSION, HEADER_FIELD_RESOURCES],
+            self,
+        )
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for HeaderDeserializer<'a> {
+    type Value = DynamicScene;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("NDJSON scene header")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut version = None;
+        let mut resources = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                HeaderField::Version => {
+                    if version.is_some() {
+                        return Err(Error::duplicate_field(HEADER_FIELD_VERSION));
+                    }
+                    version = Some(map.next_value::<u32>()?);
+                }
+                HeaderField::Resources => {
+                    if resources.is_some() {
+                        return Err(Error::duplicate_field(HEADER_FIELD_RESOURCES));
+                    }
+                    resources = Some(map.next_value_seed(ComponentVecDeserializer {
+                        registry: self.registry,
+                    })?);
+                }
+            }
+        }
+
+        let version = version.ok_or_else(|| Error::missing_field(HEADER_FIELD_VERSION))?;
+        if version != NDJSON_VERSION {
+            return Err(Error::custom(format!(
+                "unsupported NDJSON scene version {}, expected {}",
+                version, NDJSON_VERSION
+            )));
+        }
+        Ok(DynamicScene {
+            resources: resources.unwrap_or_default(),
+            entities: Vec::new(),
+            metadata: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_support::build_sample_scene, DynamicScene, SceneError};
+    use bevy_property::PropertyTypeRegistry;
+
+    #[test]
+    fn ndjson_round_trip() {
+        let scene = build_sample_scene(0, 6);
+        let registry = PropertyTypeRegistry::default();
+
+        let mut bytes = Vec::new();
+        scene.to_ndjson_writer(&mut bytes, &registry).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].contains("\"version\":1"));
+
+        let loaded = DynamicScene::from_ndjson_reader(text.as_bytes(), &registry).unwrap();
+        assert_eq!(loaded.entities.len(), 6);
+        assert_eq!(
+            loaded.content_hash(&registry).unwrap(),
+            scene.content_hash(&registry).unwrap()
+        );
+
+        let error = DynamicScene::from_ndjson_reader(&b""[..], &registry).unwrap_err();
+        assert!(matches!(error, SceneError::Json(_)));
+    }
+}
@@ -0,0 +1,631 @@
+use crate::{
+    serde::{ComponentVecDeserializer, ComponentsSerializer},
+    serialize_ron, DynamicScene, Entity, SceneMetadata,
+};
+use bevy_property::{
+    property_serde::DynamicPropertiesSerializer, DynamicProperties, Properties,
+    PropertyTypeRegistry,
+};
+use bevy_utils::HashMap;
+use serde::{
+    de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeSeq, SerializeStruct},
+    Deserialize, Serialize,
+};
+
+const PALETTE_SCENE_STRUCT: &str = "PaletteScene";
+const PALETTE_SCENE_FIELD_PALETTE: &str = "palette";
+const PALETTE_SCENE_FIELD_ENTITIES: &str = "entities";
+const PALETTE_SCENE_FIELD_RESOURCES: &str = "resources";
+const PALETTE_SCENE_FIELD_METADATA: &str = "metadata";
+const PALETTE_ENTITY_STRUCT: &str = "Entity";
+const PALETTE_ENTITY_FIELD_ENTITY: &str = "entity";
+const PALETTE_ENTITY_FIELD_TAGS: &str = "tags";
+const PALETTE_ENTITY_FIELD_COMPONENTS: &str = "components";
+
+struct PaletteEntity<'a> {
+    entity: u32,
+    tags: usize,
+    components: Vec<&'a DynamicProperties>,
+}
+
+impl DynamicScene {
+    /// Serializes the scene to RON with its tag components, the ones whose full type name passes
+    /// `is_tag`, stored in a palette: every distinct set of tag values is written once and
+    /// entities refer to it by index. This keeps scenes small when many entities share the same
+    /// tags, e.g. a team or a level layer. Resources and metadata are written like
+    /// [DynamicScene::serialize_ron] writes them. Read it back with
+    /// [DynamicScene::deserialize_ron_with_tag_palette].
+    pub fn serialize_ron_with_tag_palette(
+        &self,
+        is_tag: impl Fn(&str) -> bool,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<String, ron::Error> {
+        let mut palette = Vec::<(String, Vec<&DynamicProperties>)>::new();
+        let mut palette_indices = HashMap::<String, usize>::default();
+        let mut entities = Vec::with_capacity(self.entities.len());
+        for entity in self.entities.iter() {
+            let (tags, components) = entity
+                .components
+                .iter()
+                .partition::<Vec<_>, _>(|component| is_tag(&component.type_name));
+            let key = serialize_ron(&RefComponentsSerializer {
+                components: &tags,
+                registry,
+            })?;
+            let index = match palette_indices.get(&key) {
+                Some(index) => *index,
+                None => {
+                    palette_indices.insert(key.clone(), palette.len());
+                    palette.push((key, tags));
+                    palette.len() - 1
+                }
+            };
+            entities.push(PaletteEntity {
+                entity: entity.entity,
+                tags: index,
+                components,
+            });
+        }
+
+        serialize_ron(PaletteSceneSerializer {
+            palette: &palette,
+            entities: &entities,
+            resources: &self.resources,
+            metadata: self.metadata.as_ref(),
+            registry,
+        })
+    }
+
+    /// Reads a scene written by [DynamicScene::serialize_ron_with_tag_palette]. Each entity gets
+    /// a copy of its palette entry's tags, and entities sharing a palette entry are grouped
+    /// together so they end up next to each other when written to a world.
+    pub fn deserialize_ron_with_tag_palette(
+        ron: &str,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<DynamicScene, ron::Error> {
+        let mut deserializer = ron::de::Deserializer::from_str(ron)?;
+        let PaletteScene {
+            palette,
+            mut entities,
+            resources,
+            metadata,
+        } = PaletteSceneDeserializer { registry }.deserialize(&mut deserializer)?;
+        entities.sort_by_key(|(tags, _)| *tags);
+
+        let mut scene = DynamicScene {
+            resources,
+            metadata,
+            ..Default::default()
+        };
+        for (tags, mut entity) in entities {
+            let tags = palette
+                .get(tags)
+                .ok_or_else(|| ron::Error::custom(format!("tag palette has no entry {}", tags)))?;
+            entity
+                .components
+                .extend(tags.iter().map(|tag| tag.to_dynamic()));
+            scene.entities.push(entity);
+        }
+        Ok(scene)
+    }
+}
+
+struct PaletteSceneSerializer<'a> {
+    palette: &'a [(String, Vec<&'a DynamicProperties>)],
+    entities: &'a [PaletteEntity<'a>],
+    resources: &'a [DynamicProperties],
+    metadata: Option<&'a SceneMetadata>,
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a> Serialize for PaletteSceneSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut field_count = 2;
+        if !self.resources.is_empty() {
+            field_count += 1;
+        }
+        if self.metadata.is_some() {
+            field_count += 1;
+        }
+        let mut state = serializer.serialize_struct(PALETTE_SCENE_STRUCT, field_count)?;
+        state.serialize_field(
+            PALETTE_SCENE_FIELD_PALETTE,
+            &PaletteSerializer {
+                palette: self.palette,
+                registry: self.registry,
+            },
+        )?;
+        state.serialize_field(
+            PALETTE_SCENE_FIELD_ENTITIES,
+            &PaletteEntitiesSerializer {
+                entities: self.entities,
+                registry: self.registry,
+            },
+        )?;
+        if !self.resources.is_empty() {
+            state.serialize_field(
+                PALETTE_SCENE_FIELD_RESOURCES,
+                &ComponentsSerializer {
+                    components: self.resources,
+                    registry: self.registry,
+                },
+            )?;
+        }
+        if let Some(metadata) = self.metadata {
+            state.serialize_field(PALETTE_SCENE_FIELD_METADATA, metadata)?;
+        }
+        state.end()
+    }
+}
+
+struct PaletteSerializer<'a> {
+    palette: &'a [(String, Vec<&'a DynamicProperties>)],
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a> Serialize for PaletteSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_seq(Some(self.palette.len()))?;
+        for (_, tags) in self.palette.iter() {
+            state.serialize_element(&RefComponentsSerializer {
+                components: tags,
+                registry: self.registry,
+            })?;
+        }
+        state.end()
+    }
+}
+
+struct PaletteEntitiesSerializer<'a> {
+    entities: &'a [PaletteEntity<'a>],
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a> Serialize for PaletteEntitiesSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_seq(Some(self.entities.len()))?;
+        for entity in self.entities.iter() {
+            state.serialize_element(&PaletteEntitySerializer {
+                entity,
+                registry: self.registry,
+            })?;
+        }
+        state.end()
+    }
+}
+
+struct PaletteEntitySerializer<'a> {
+    entity: &'a PaletteEntity<'a>,
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a> Serialize for PaletteEntitySerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct(PALETTE_ENTITY_STRUCT, 3)?;
+        state.serialize_field(PALETTE_ENTITY_FIELD_ENTITY, &self.entity.entity)?;
+        state.serialize_field(PALETTE_ENTITY_FIELD_TAGS, &self.entity.tags)?;
+        state.serialize_field(
+            PALETTE_ENTITY_FIELD_COMPONENTS,
+            &RefComponentsSerializer {
+                components: &self.entity.components,
+                registry: self.registry,
+            },
+        )?;
+        state.end()
+    }
+}
+
+/// Like [crate::serde::ComponentsSerializer], for components borrowed from different entities.
+struct RefComponentsSerializer<'a> {
+    components: &'a [&'a DynamicProperties],
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a> Serialize for RefComponentsSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_seq(Some(self.components.len()))?;
+        for component in self.components.iter() {
+            state.serialize_element(&DynamicPropertiesSerializer::new(component, self.registry))?;
+        }
+        state.end()
+    }
+}
+
+struct PaletteScene {
+    palette: Vec<Vec<DynamicProperties>>,
+    entities: Vec<(usize, Entity)>,
+    resources: Vec<DynamicProperties>,
+    metadata: Option<SceneMetadata>,
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum PaletteSceneField {
+    Palette,
+    Entities,
+    Resources,
+    Metadata,
+}
+
+struct PaletteSceneDeserializer<'a> {
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for PaletteSceneDeserializer<'a> {
+    type Value = PaletteScene;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            PALETTE_SCENE_STRUCT,
+            &[
+                PALETTE_SCENE_FIELD_PALETTE,
+                PALETTE_SCENE_FIELD_ENTITIES,
+                PALETTE_SCENE_FIELD_RESOURCES,
+                PALETTE_SCENE_FIELD_METADATA,
+            ],
+            self,
+        )
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for PaletteSceneDeserializer<'a> {
+    type Value = PaletteScene;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("palette scene struct")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut palette = None;
+        let mut entities = None;
+        let mut resources = None;
+        let mut metadata = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                PaletteSceneField::Palette => {
+                    if palette.is_some() {
+                        return Err(Error::duplicate_field(PALETTE_SCENE_FIELD_PALETTE));
+                    }
+                    palette = Some(map.next_value_seed(PaletteDeserializer {
+                        registry: self.registry,
+                    })?);
+                }
+                PaletteSceneField::Entities => {
+                    if entities.is_some() {
+                        return Err(Error::duplicate_field(PALETTE_SCENE_FIELD_ENTITIES));
+                    }
+                    entities = Some(map.next_value_seed(PaletteEntitiesDeserializer {
+                        registry: self.registry,
+                    })?);
+                }
+                PaletteSceneField::Resources => {
+                    if resources.is_some() {
+                        return Err(Error::duplicate_field(PALETTE_SCENE_FIELD_RESOURCES));
+                    }
+                    resources = Some(map.next_value_seed(ComponentVecDeserializer {
+                        registry: self.registry,
+                    })?);
+                }
+                PaletteSceneField::Metadata => {
+                    if metadata.is_some() {
+                        return Err(Error::duplicate_field(PALETTE_SCENE_FIELD_METADATA));
+                    }
+                    metadata = Some(map.next_value::<SceneMetadata>()?);
+                }
+            }
+        }
+
+        let palette = palette.ok_or_else(|| Error::missing_field(PALETTE_SCENE_FIELD_PALETTE))?;
+        let entities =
+            entities.ok_or_else(|| Error::missing_field(PALETTE_SCENE_FIELD_ENTITIES))?;
+        Ok(PaletteScene {
+            palette,
+            entities,
+            resources: resources.unwrap_or_default(),
+            metadata,
+        })
+    }
+}
+
+struct PaletteDeserializer<'a> {
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for PaletteDeserializer<'a> {
+    type Value = Vec<Vec<DynamicProperties>>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for PaletteDeserializer<'a> {
+    type Value = Vec<Vec<DynamicProperties>>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("list of tag sets")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut palette = Vec::new();
+        while let Some(tags) = seq.next_element_seed(ComponentVecDeserializer {
+            registry: self.registry,
+        })? {
+            palette.push(tags);
+        }
+        Ok(palette)
+    }
+}
+
+struct PaletteEntitiesDeserializer<'a> {
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for PaletteEntitiesDeserializer<'a> {
+    type Value = Vec<(usize, Entity)>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for PaletteEntitiesDeserializer<'a> {
+    type Value = Vec<(usize, Entity)>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("list of entities")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut entities = Vec::new();
+        while let Some(entity) = seq.next_element_seed(PaletteEntityDeserializer {
+            registry: self.registry,
+        })? {
+            entities.push(entity);
+        }
+        Ok(entities)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum PaletteEntityField {
+    Entity,
+    Tags,
+    Components,
+}
+
+struct PaletteEntityDeserializer<'a> {
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for PaletteEntityDeserializer<'a> {
+    type Value = (usize, Entity);
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            PALETTE_ENTITY_STRUCT,
+            &[
+                PALETTE_ENTITY_FIELD_ENTITY,
+                PALETTE_ENTITY_FIELD_TAGS,
+                PALETTE_ENTITY_FIELD_COMPONENTS,
+            ],
+            self,
+        )
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for PaletteEntityDeserializer<'a> {
+    type Value = (usize, Entity);
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("entity struct")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut id = None;
+        let mut tags = None;
+        let mut components = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                PaletteEntityField::Entity => {
+                    if id.is_some() {
+                        return Err(Error::duplicate_field(PALETTE_ENTITY_FIELD_ENTITY));
+                    }
+                    id = Some(map.next_value::<u32>()?);
+                }
+                PaletteEntityField::Tags => {
+                    if tags.is_some() {
+                        return Err(Error::duplicate_field(PALETTE_ENTITY_FIELD_TAGS));
+                    }
+                    tags = Some(map.next_value::<usize>()?);
+                }
+                PaletteEntityField::Components => {
+                    if components.is_some() {
+                        return Err(Error::duplicate_field(PALETTE_ENTITY_FIELD_COMPONENTS));
+                    }
+                    components = Some(map.next_value_seed(ComponentVecDeserializer {
+                        registry: self.registry,
+                    })?);
+                }
+            }
+        }
+
+        let entity = id.ok_or_else(|| Error::missing_field(PALETTE_ENTITY_FIELD_ENTITY))?;
+        let tags = tags.ok_or_else(|| Error::missing_field(PALETTE_ENTITY_FIELD_TAGS))?;
+        let components =
+            components.ok_or_else(|| Error::missing_field(PALETTE_ENTITY_FIELD_COMPONENTS))?;
+        Ok((tags, Entity { entity, components }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_support::{sample_type_registry, SamplePosition},
+        DynamicScene,
+    };
+    use bevy_ecs::World;
+    use bevy_property::{Properties, PropertiesVal};
+
+    #[derive(Properties, Default)]
+    struct Team {
+        name: String,
+    }
+
+    fn team(name: &str) -> Team {
+        Team {
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn tag_palette() {
+        let type_registry = sample_type_registry();
+        type_registry.component.write().register::<Team>();
+        let property_type_registry = type_registry.property.read();
+        let mut world = World::default();
+        world.spawn((
+            SamplePosition {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            team("red"),
+        ));
+        world.spawn((
+            SamplePosition {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            team("blue"),
+        ));
+        world.spawn((
+            SamplePosition {
+                x: 2.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            team("red"),
+        ));
+        world.spawn((
+            SamplePosition {
+                x: 3.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            team("red"),
+        ));
+        let scene =
+            DynamicScene::from_world_in_spawn_order(&world, &type_registry.component.read());
+
+        let is_tag = |type_name: &str| type_name == std::any::type_name::<Team>();
+        let ron = scene
+            .serialize_ron_with_tag_palette(is_tag, &property_type_registry)
+            .unwrap();
+        assert_eq!(ron.matches("\"red\"").count(), 1);
+        assert_eq!(ron.matches("\"blue\"").count(), 1);
+        assert!(ron.len() < scene.serialize_ron(&property_type_registry).unwrap().len());
+
+        let loaded =
+            DynamicScene::deserialize_ron_with_tag_palette(&ron, &property_type_registry).unwrap();
+        let teams = loaded
+            .entities
+            .iter()
+            .map(|entity| {
+                let team = entity
+                    .components
+                    .iter()
+                    .find(|component| is_tag(&component.type_name))
+                    .unwrap();
+                (
+                    entity.entity,
+                    team.prop_val::<String>("name").unwrap().clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let expected = [(0, "red"), (2, "red"), (3, "red"), (1, "blue")]
+            .iter()
+            .map(|(entity, team)| (*entity, team.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(teams, expected);
+        assert!(loaded
+            .entities
+            .iter()
+            .all(|entity| entity.components.len() == 2));
+    }
+
+    #[test]
+    fn tag_palette_resources_and_metadata() {
+        let type_registry = sample_type_registry();
+        type_registry.component.write().register::<Team>();
+        let property_type_registry = type_registry.property.read();
+        let mut world = World::default();
+        world.spawn((SamplePosition::default(), team("red")));
+        let mut scene =
+            DynamicScene::from_world_with_metadata(&world, &type_registry.component.read());
+        scene.resources.push(
+            SamplePosition {
+                x: 4.0,
+                y: 5.0,
+                z: 6.0,
+            }
+            .to_dynamic(),
+        );
+
+        let is_tag = |type_name: &str| type_name == std::any::type_name::<Team>();
+        let ron = scene
+            .serialize_ron_with_tag_palette(is_tag, &property_type_registry)
+            .unwrap();
+        let loaded =
+            DynamicScene::deserialize_ron_with_tag_palette(&ron, &property_type_registry).unwrap();
+        assert_eq!(loaded.resources.len(), 1);
+        assert_eq!(*loaded.resources[0].prop_val::<f32>("z").unwrap(), 6.0);
+        assert_eq!(loaded.metadata, scene.metadata);
+        assert_eq!(
+            loaded.metadata.map(|metadata| metadata.entity_count),
+            Some(1)
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{
+    serde::{SCENE_FIELD_ENTITIES, SCENE_FIELD_RESOURCES},
+    DynamicScene, SceneError,
+};
+use serde::{
+    de::{Error, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::fmt;
+
+/// The top-level layout of a serialized scene.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SceneLayout {
+    /// A bare list of entities, as written for scenes without resources.
+    EntityList,
+    /// A struct with `resources` and `entities` fields.
+    Struct,
+}
+
+/// The format features a serialized scene uses, as reported by [DynamicScene::probe].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SceneProbe {
+    pub layout: SceneLayout,
+    pub has_resources: bool,
+    /// Whether the entities are stored as a map keyed by entity id instead of a list.
+    pub entities_as_map: bool,
+    pub entity_count: usize,
+}
+
+impl DynamicScene {
+    /// Inspects a RON scene file without building any of its components. Component bodies are
+    /// skipped over, so this is much cheaper than fully deserializing the scene.
+    pub fn probe(bytes: &[u8]) -> Result<SceneProbe, SceneError> {
+        let mut deserializer = ron::de::Deserializer::from_bytes(bytes)?;
+        Ok(SceneProbe::deserialize(&mut deserializer)?)
+    }
+}
+
+impl<'de> Deserialize<'de> for SceneProbe {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SceneProbeVisiter)
+    }
+}
+
+struct SceneProbeVisiter;
+
+impl<'de> Visitor<'de> for SceneProbeVisiter {
+    type Value = SceneProbe;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("scene")
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let entities = EntitiesProbeVisiter.visit_seq(seq)?;
+        Ok(SceneProbe {
+            layout: SceneLayout::EntityList,
+            has_resources: false,
+            entities_as_map: entities.as_map,
+            entity_count: entities.count,
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut has_resources = false;
+        let mut entities = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                SCENE_FIELD_RESOURCES => {
+                    has_resources = map.next_value::<CountProbe>()?.0 > 0;
+                }
+                SCENE_FIELD_ENTITIES => {
+                    entities = Some(map.next_value::<EntitiesProbe>()?);
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let entities = entities.ok_or_else(|| Error::missing_field(SCENE_FIELD_ENTITIES))?;
+        Ok(SceneProbe {
+            layout: SceneLayout::Struct,
+            has_resources,
+            entities_as_map: entities.as_map,
+            entity_count: entities.count,
+        })
+    }
+}
+
+struct EntitiesProbe {
+    as_map: bool,
+    count: usize,
+}
+
+impl<'de> Deserialize<'de> for EntitiesProbe {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(EntitiesProbeVisiter)
+    }
+}
+
+struct EntitiesProbeVisiter;
+
+impl<'de> Visitor<'de> for EntitiesProbeVisiter {
+    type Value = EntitiesProbe;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("list or map of entities")
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        Ok(EntitiesProbe {
+            as_map: false,
+            count: CountVisiter.visit_seq(seq)?.0,
+        })
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        Ok(EntitiesProbe {
+            as_map: true,
+            count: CountVisiter.visit_map(map)?.0,
+        })
+    }
+}
+
+/// Counts the entries of a list or map while skipping over their contents.
+struct CountProbe(usize);
+
+impl<'de> Deserialize<'de> for CountProbe {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(CountVisiter)
+    }
+}
+
+struct CountVisiter;
+
+impl<'de> Visitor<'de> for CountVisiter {
+    type Value = CountProbe;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("list or map")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut count = 0;
+        while seq.next_element::<IgnoredAny>()?.is_some() {
+            count += 1;
+        }
+        Ok(CountProbe(count))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut count = 0;
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {
+            count += 1;
+        }
+        Ok(CountProbe(count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SceneLayout;
+    use crate::{
+        serde::SceneSerializer,
+        serialize_ron,
+        test_support::{sample_type_registry, SamplePosition},
+        DynamicScene,
+    };
+    use bevy_ecs::{Resources, World};
+    use bevy_property::Properties;
+    use bevy_type_registry::TypeRegistry;
+
+    #[derive(Properties, Default)]
+    struct Score {
+        value: u32,
+    }
+
+    fn build_scene(type_registry: &TypeRegistry) -> DynamicScene {
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 1.0,
+            y: 2.0,
+            z: 0.0,
+        },));
+        world.spawn((SamplePosition {
+            x: 3.0,
+            y: 4.0,
+            z: 0.0,
+        },));
+        DynamicScene::from_world(&world, &type_registry.component.read())
+    }
+
+    fn type_registry() -> TypeRegistry {
+        let type_registry = sample_type_registry();
+        type_registry.resource.write().register::<Score>();
+        type_registry
+    }
+
+    #[test]
+    fn probe_entity_list() {
+        let type_registry = type_registry();
+        let ron = build_scene(&type_registry)
+            .serialize_ron(&type_registry.property.read())
+            .unwrap();
+        let probe = DynamicScene::probe(ron.as_bytes()).unwrap();
+        assert_eq!(probe.layout, SceneLayout::EntityList);
+        assert!(!probe.has_resources);
+        assert!(!probe.entities_as_map);
+        assert_eq!(probe.entity_count, 2);
+    }
+
+    #[test]
+    fn probe_resources() {
+        let type_registry = type_registry();
+        let mut resources = Resources::default();
+        resources.insert(Score { value: 10 });
+        let mut scene = build_scene(&type_registry);
+        scene.add_resources(&resources, &type_registry.resource.read());
+        let ron = scene.serialize_ron(&type_registry.property.read()).unwrap();
+        let probe = DynamicScene::probe(ron.as_bytes()).unwrap();
+        assert_eq!(probe.layout, SceneLayout::Struct);
+        assert!(probe.has_resources);
+        assert!(!probe.entities_as_map);
+        assert_eq!(probe.entity_count, 2);
+    }
+
+    #[test]
+    fn probe_entities_as_map() {
+        let type_registry = type_registry();
+        let scene = build_scene(&type_registry);
+        let property_type_registry = type_registry.property.read();
+        let ron = serialize_ron(
+            SceneSerializer::new(&scene, &property_type_registry).with_entities_as_map(),
+        )
+        .unwrap();
+        let probe = DynamicScene::probe(ron.as_bytes()).unwrap();
+        assert_eq!(probe.layout, SceneLayout::Struct);
+        assert!(!probe.has_resources);
+        assert!(probe.entities_as_map);
+        assert_eq!(probe.entity_count, 2);
+    }
+
+    #[test]
+    fn probe_invalid() {
+        assert!(DynamicScene::probe(b"42").is_err());
+    }
+}
@@ -36,6 +36,8 @@ pub struct SceneSpawner {
 pub enum SceneSpawnError {
     #[error("Scene contains an unregistered component.")]
     UnregisteredComponent { type_name: String },
+    #[error("Scene contains a component that can't be loaded: {reason}")]
+    InvalidComponent { type_name: String, reason: String },
     #[error("Scene does not exist. Perhaps it is still loading?")]
     NonExistentScene { handle: Handle<DynamicScene> },
     #[error("Scene does not exist. Perhaps it is still loading?")]
@@ -121,6 +123,12 @@ impl SceneSpawner {
                     .ok_or(SceneSpawnError::UnregisteredComponent {
                         type_name: component.type_name.to_string(),
                     })?;
+                component_registration
+                    .validate_property(component)
+                    .map_err(|reason| SceneSpawnError::InvalidComponent {
+                        type_name: component.type_name.to_string(),
+                        reason,
+                    })?;
                 if world.has_component_type(entity, component_registration.ty) {
                     if component.type_name != "Camera" {
                         component_registration.apply_property_to_entity(world, entity, component);
@@ -129,6 +137,7 @@ impl SceneSpawner {
                     component_registration
                         .add_property_to_entity(world, resources, entity, component);
                 }
+                component_registration.resolve_component(world, resources, entity);
             }
         }
         Ok(())
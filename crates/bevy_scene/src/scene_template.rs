@@ -0,0 +1,134 @@
+use crate::{DynamicScene, Entity, SceneError};
+use bevy_ecs::{Entity as WorldEntity, World};
+use bevy_property::{DynamicProperties, Properties, PropertyType, PropertyTypeRegistry};
+use bevy_type_registry::ComponentRegistry;
+
+/// Serializes the registered components of `entity` as a standalone single entity RON scene that
+/// can be used as a prefab. Fields listed in a registration's `instance_fields` are left out, so
+/// each instance gets them from the component's `FromResources` value. Load the template with
+/// [DynamicScene::deserialize_from_slice] and write it to a world once per instance.
+pub fn serialize_template(
+    world: &World,
+    entity: WorldEntity,
+    component_registry: &ComponentRegistry,
+    registry: &PropertyTypeRegistry,
+) -> Result<String, SceneError> {
+    let location = world
+        .get_entity_location(entity)
+        .ok_or_else(|| SceneError::NoSuchEntity(entity.id()))?;
+    let archetype = world.archetypes().nth(location.archetype as usize).unwrap();
+    let components = archetype
+        .types()
+        .iter()
+        .filter_map(|type_info| component_registry.get(&type_info.id()))
+        .map(|registration| {
+            let component = registration
+                .get_serialized_component(archetype, location.index as usize)
+                .map_err(|reason| SceneError::UnserializableComponent {
+                    type_name: registration.long_name.to_string(),
+                    reason,
+                })?;
+            if registration.instance_fields.is_empty()
+                || component.property_type() != PropertyType::Map
+            {
+                return Ok(component);
+            }
+
+            let mut template = DynamicProperties::map();
+            template.type_name = component.type_name.clone();
+            for (index, field) in component.iter_props().enumerate() {
+                let name = component.prop_name(index).unwrap();
+                if !registration
+                    .instance_fields
+                    .iter()
+                    .any(|instance_field| *instance_field == name)
+                {
+                    template.set_box(name, field.clone_prop());
+                }
+            }
+            Ok(template)
+        })
+        .collect::<Result<_, SceneError>>()?;
+
+    let scene = DynamicScene {
+        entities: vec![Entity {
+            entity: 0,
+            components,
+        }],
+        ..Default::default()
+    };
+    Ok(scene.serialize_ron(registry)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::serialize_template;
+    use crate::{test_support::SamplePosition, DynamicScene};
+    use bevy_ecs::{Resources, World};
+    use bevy_property::Properties;
+    use bevy_type_registry::{ComponentRegistration, TypeRegistry};
+
+    #[derive(Properties, Default, Debug, PartialEq)]
+    struct Enemy {
+        kind: String,
+        speed: f32,
+        id: u32,
+    }
+
+    #[test]
+    fn serialize_and_instantiate_template() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().add_registration(
+            ComponentRegistration::build::<Enemy>()
+                .with_instance_fields(&["id"])
+                .finish(),
+        );
+        type_registry.component.write().add_registration(
+            ComponentRegistration::build::<SamplePosition>()
+                .with_instance_fields(&["x", "y", "z"])
+                .finish(),
+        );
+        let mut world = World::default();
+        let entity = world.spawn((
+            Enemy {
+                kind: "goblin".to_string(),
+                speed: 2.5,
+                id: 42,
+            },
+            SamplePosition {
+                x: 3.0,
+                y: 4.0,
+                z: 0.0,
+            },
+        ));
+
+        let template = serialize_template(
+            &world,
+            entity,
+            &type_registry.component.read(),
+            &type_registry.property.read(),
+        )
+        .unwrap();
+        assert!(template.contains("\"goblin\""));
+        assert!(!template.contains("\"id\""));
+        assert!(!template.contains("\"x\""));
+
+        let prefab = DynamicScene::deserialize_from_slice(
+            template.as_bytes(),
+            &type_registry.property.read(),
+        )
+        .unwrap();
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut instances = World::default();
+        for _ in 0..3 {
+            prefab.write_to_world(&mut instances, &resources).unwrap();
+        }
+
+        let spawned = instances
+            .query::<(&Enemy, &SamplePosition)>()
+            .map(|(enemy, position)| (enemy.kind.clone(), enemy.speed, enemy.id, position.x))
+            .collect::<Vec<_>>();
+        assert_eq!(spawned, vec![("goblin".to_string(), 2.5, 0, 0.0); 3]);
+    }
+}
@@ -1,23 +1,104 @@
-use crate::{DynamicScene, Entity};
+use crate::{serialize_ron, DynamicScene, Entity, SceneError, SceneMetadata};
 use anyhow::Result;
+use bevy_ecs::Archetype;
 use bevy_property::{
-    property_serde::{DynamicPropertiesDeserializer, DynamicPropertiesSerializer},
-    DynamicProperties, PropertyTypeRegistry,
+    property_serde::{
+        DynamicPropertiesDeserializer, DynamicPropertiesSerializer, MapPropertyDeserializer,
+        MapValueSerializer,
+    },
+    DynamicProperties, PropertyType, PropertyTypeRegistry,
 };
+use bevy_type_registry::{ComponentRegistration, ComponentRegistry};
+use bevy_utils::HashSet;
 use serde::{
-    de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor},
-    ser::{SerializeSeq, SerializeStruct},
+    de::{DeserializeSeed, Error, IgnoredAny, MapAccess, SeqAccess, Unexpected, Visitor},
+    ser::{SerializeMap, SerializeSeq, SerializeStruct, SerializeTuple},
     Deserialize, Serialize,
 };
+use std::{
+    cell::RefCell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// The largest entity count passed to the serializer as a length hint by default. Larger counts
+/// are serialized without a hint, for formats that store lengths in 32 bits.
+pub const DEFAULT_MAX_LENGTH_HINT: usize = u32::MAX as usize;
 
+/// The error message serde formats see when serialization stops because the cancel flag passed
+/// to [SceneSerializer::with_cancel_flag] was set. [SceneSerializer::serialize_ron] reports it as
+/// [SceneError::Cancelled].
+pub const SERIALIZATION_CANCELLED: &str = "scene serialization was cancelled";
+
+/// Serializes a [DynamicScene] without modifying it. The serializer itself is not `Sync` because of
+/// the optional progress callback, so create one per thread when serializing in parallel.
 pub struct SceneSerializer<'a> {
     pub scene: &'a DynamicScene,
     pub registry: &'a PropertyTypeRegistry,
+    progress: Option<RefCell<Box<dyn FnMut(usize, usize) + 'a>>>,
+    cancel: Option<&'a AtomicBool>,
+    entities_as_map: bool,
+    entity_hashes: bool,
+    max_length_hint: usize,
 }
 
 impl<'a> SceneSerializer<'a> {
     pub fn new(scene: &'a DynamicScene, registry: &'a PropertyTypeRegistry) -> Self {
-        SceneSerializer { scene, registry }
+        SceneSerializer {
+            scene,
+            registry,
+            progress: None,
+            cancel: None,
+            entities_as_map: false,
+            entity_hashes: false,
+            max_length_hint: DEFAULT_MAX_LENGTH_HINT,
+        }
+    }
+
+    /// Calls `progress` with the number of serialized entities and the total entity count after
+    /// each entity is serialized.
+    pub fn with_progress(mut self, progress: impl FnMut(usize, usize) + 'a) -> Self {
+        self.progress = Some(RefCell::new(Box::new(progress)));
+        self
+    }
+
+    /// Checks `cancel` before each entity is serialized and stops once it is set. Use
+    /// [SceneSerializer::serialize_ron] to get the cancellation as [SceneError::Cancelled].
+    pub fn with_cancel_flag(mut self, cancel: &'a AtomicBool) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Writes the entities as a map from entity id to components instead of a list. Only use this
+    /// with formats that keep map entries in order (JSON objects, for example, might not), since
+    /// the entities are otherwise reloaded in an arbitrary order.
+    pub fn with_entities_as_map(mut self) -> Self {
+        self.entities_as_map = true;
+        self
+    }
+
+    /// Adds a `hash` field to every entity holding [Entity::component_hash], so a later save can
+    /// find changed entities by comparing hashes. Loaders ignore the field, and
+    /// [DynamicScene::content_hash] is computed without it. Entities written as a map get a
+    /// `(components, hash)` struct as their value instead of the list of components.
+    pub fn with_entity_hashes(mut self) -> Self {
+        self.entity_hashes = true;
+        self
+    }
+
+    /// Omits the entity count hint when the scene has more than `max_length_hint` entities.
+    pub fn with_max_length_hint(mut self, max_length_hint: usize) -> Self {
+        self.max_length_hint = max_length_hint;
+        self
+    }
+
+    /// Serializes the scene to RON, failing with [SceneError::Cancelled] if the cancel flag was
+    /// set.
+    pub fn serialize_ron(self) -> Result<String, SceneError> {
+        let cancel = self.cancel;
+        serialize_ron(self).map_err(|error| match cancel {
+            Some(cancel) if cancel.load(Ordering::Relaxed) => SceneError::Cancelled,
+            _ => error.into(),
+        })
     }
 }
 
@@ -26,28 +107,137 @@ impl<'a> Serialize for SceneSerializer<'a> {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_seq(Some(self.scene.entities.len()))?;
-        for entity in self.scene.entities.iter() {
-            state.serialize_element(&EntitySerializer {
-                entity,
+        let report_progress = |done: usize, total: usize| {
+            if let Some(progress) = &self.progress {
+                (&mut *progress.borrow_mut())(done, total);
+            }
+        };
+        let entities = EntitiesSerializer {
+            entities: &self.scene.entities,
+            registry: self.registry,
+            progress: Some(&report_progress),
+            cancel: self.cancel,
+            as_map: self.entities_as_map,
+            entity_hashes: self.entity_hashes,
+            max_length_hint: self.max_length_hint,
+        };
+
+        // scenes without resources keep the original "list of entities" layout
+        if self.scene.resources.is_empty() && !self.entities_as_map && self.scene.metadata.is_none()
+        {
+            return entities.serialize(serializer);
+        }
+
+        let field_count = if self.scene.metadata.is_some() { 3 } else { 2 };
+        let mut state = serializer.serialize_struct(SCENE_STRUCT, field_count)?;
+        state.serialize_field(
+            SCENE_FIELD_RESOURCES,
+            &ComponentsSerializer {
+                components: &self.scene.resources,
                 registry: self.registry,
-            })?;
+            },
+        )?;
+        state.serialize_field(SCENE_FIELD_ENTITIES, &entities)?;
+        if let Some(metadata) = &self.scene.metadata {
+            state.serialize_field(SCENE_FIELD_METADATA, metadata)?;
         }
         state.end()
     }
 }
 
-pub struct EntitySerializer<'a> {
-    pub entity: &'a Entity,
+pub struct EntitiesSerializer<'a> {
+    pub entities: &'a [Entity],
     pub registry: &'a PropertyTypeRegistry,
+    pub progress: Option<&'a dyn Fn(usize, usize)>,
+    pub cancel: Option<&'a AtomicBool>,
+    /// Writes a map from entity id to components instead of a list of entities.
+    pub as_map: bool,
+    /// Adds each entity's [Entity::component_hash] as a `hash` field.
+    pub entity_hashes: bool,
+    /// Entity counts above this are serialized without a length hint.
+    pub max_length_hint: usize,
 }
 
-impl<'a> Serialize for EntitySerializer<'a> {
+impl<'a> EntitiesSerializer<'a> {
+    fn check_cancelled<E: serde::ser::Error>(&self) -> Result<(), E> {
+        match self.cancel {
+            Some(cancel) if cancel.load(Ordering::Relaxed) => {
+                Err(E::custom(SERIALIZATION_CANCELLED))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn length_hint(&self) -> Option<usize> {
+        Some(self.entities.len()).filter(|len| *len <= self.max_length_hint)
+    }
+
+    fn report_progress(&self, index: usize) {
+        if let Some(progress) = self.progress {
+            progress(index + 1, self.entities.len());
+        }
+    }
+}
+
+impl<'a> Serialize for EntitiesSerializer<'a> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct(ENTITY_STRUCT, 2)?;
+        if self.as_map {
+            let mut state = serializer.serialize_map(self.length_hint())?;
+            for (index, entity) in self.entities.iter().enumerate() {
+                self.check_cancelled()?;
+                let key = entity.entity.to_string();
+                let components = ComponentsSerializer {
+                    components: &entity.components,
+                    registry: self.registry,
+                };
+                if self.entity_hashes {
+                    let hash = entity
+                        .component_hash(self.registry)
+                        .map_err(serde::ser::Error::custom)?;
+                    state
+                        .serialize_entry(&key, &HashedComponentsSerializer { components, hash })?;
+                } else {
+                    state.serialize_entry(&key, &components)?;
+                }
+                self.report_progress(index);
+            }
+            return state.end();
+        }
+
+        let mut state = serializer.serialize_seq(self.length_hint())?;
+        for (index, entity) in self.entities.iter().enumerate() {
+            self.check_cancelled()?;
+            let entity_serializer = EntitySerializer {
+                entity,
+                registry: self.registry,
+            };
+            if self.entity_hashes {
+                let hash = entity
+                    .component_hash(self.registry)
+                    .map_err(serde::ser::Error::custom)?;
+                state.serialize_element(&HashedEntitySerializer {
+                    entity_serializer,
+                    hash,
+                })?;
+            } else {
+                state.serialize_element(&entity_serializer)?;
+            }
+            self.report_progress(index);
+        }
+        state.end()
+    }
+}
+
+pub struct EntitySerializer<'a> {
+    pub entity: &'a Entity,
+    pub registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a> EntitySerializer<'a> {
+    fn serialize_fields<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error> {
         state.serialize_field(ENTITY_FIELD_ENTITY, &self.entity.entity)?;
         state.serialize_field(
             ENTITY_FIELD_COMPONENTS,
@@ -55,7 +245,53 @@ impl<'a> Serialize for EntitySerializer<'a> {
                 components: &self.entity.components,
                 registry: self.registry,
             },
-        )?;
+        )
+    }
+}
+
+impl<'a> Serialize for EntitySerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct(ENTITY_STRUCT, 2)?;
+        self.serialize_fields(&mut state)?;
+        state.end()
+    }
+}
+
+/// An [EntitySerializer] followed by a `hash` field.
+struct HashedEntitySerializer<'a> {
+    entity_serializer: EntitySerializer<'a>,
+    hash: u64,
+}
+
+impl<'a> Serialize for HashedEntitySerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct(ENTITY_STRUCT, 3)?;
+        self.entity_serializer.serialize_fields(&mut state)?;
+        state.serialize_field(ENTITY_FIELD_HASH, &self.hash)?;
+        state.end()
+    }
+}
+
+/// The components of an entity written as a map value, followed by their hash.
+struct HashedComponentsSerializer<'a> {
+    components: ComponentsSerializer<'a>,
+    hash: u64,
+}
+
+impl<'a> Serialize for HashedComponentsSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct(HASHED_COMPONENTS_STRUCT, 2)?;
+        state.serialize_field(ENTITY_FIELD_COMPONENTS, &self.components)?;
+        state.serialize_field(ENTITY_FIELD_HASH, &self.hash)?;
         state.end()
     }
 }
@@ -81,6 +317,8 @@ impl<'a> Serialize for ComponentsSerializer<'a> {
     }
 }
 
+/// Deserializes a [DynamicScene] written by [SceneSerializer]. Scenes and their components are
+/// read with `deserialize_any`, so only self-describing formats such as RON or JSON are supported.
 pub struct SceneDeserializer<'a> {
     pub property_type_registry: &'a PropertyTypeRegistry,
 }
@@ -92,24 +330,183 @@ impl<'a, 'de> DeserializeSeed<'de> for SceneDeserializer<'a> {
     where
         D: serde::Deserializer<'de>,
     {
-        let mut scene = DynamicScene::default();
-        scene.entities = deserializer.deserialize_seq(SceneEntitySeqVisiter {
+        deserializer.deserialize_any(SceneVisiter {
             property_type_registry: self.property_type_registry,
-        })?;
+        })
+    }
+}
+
+impl<'a> SceneDeserializer<'a> {
+    /// Only keeps the components whose type name passes `component_filter`, so they are never
+    /// inserted when the scene is written to a world. This is useful for loading a scene into a
+    /// restricted context, such as a preview.
+    pub fn with_component_filter<F>(self, component_filter: F) -> FilteredSceneDeserializer<'a, F>
+    where
+        F: Fn(&str) -> bool,
+    {
+        FilteredSceneDeserializer {
+            scene_deserializer: self,
+            component_filter,
+        }
+    }
+}
+
+pub struct FilteredSceneDeserializer<'a, F> {
+    pub scene_deserializer: SceneDeserializer<'a>,
+    pub component_filter: F,
+}
+
+impl<'a, 'de, F> DeserializeSeed<'de> for FilteredSceneDeserializer<'a, F>
+where
+    F: Fn(&str) -> bool,
+{
+    type Value = DynamicScene;
 
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let mut scene = self.scene_deserializer.deserialize(deserializer)?;
+        for entity in scene.entities.iter_mut() {
+            entity
+                .components
+                .retain(|component| (self.component_filter)(&component.type_name));
+        }
         Ok(scene)
     }
 }
 
-struct SceneEntitySeqVisiter<'a> {
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum SceneField {
+    Resources,
+    Entities,
+    Metadata,
+}
+
+pub const SCENE_STRUCT: &str = "Scene";
+pub const SCENE_FIELD_RESOURCES: &str = "resources";
+pub const SCENE_FIELD_ENTITIES: &str = "entities";
+pub const SCENE_FIELD_METADATA: &str = "metadata";
+
+struct SceneVisiter<'a> {
+    pub property_type_registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> Visitor<'de> for SceneVisiter<'a> {
+    type Value = DynamicScene;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("scene")
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let entities = SceneEntitiesVisiter {
+            property_type_registry: self.property_type_registry,
+        }
+        .visit_seq(seq)?;
+
+        Ok(DynamicScene {
+            entities,
+            resources: Vec::new(),
+            metadata: None,
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut resources = None;
+        let mut entities = None;
+        let mut metadata = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                SceneField::Resources => {
+                    if resources.is_some() {
+                        return Err(Error::duplicate_field(SCENE_FIELD_RESOURCES));
+                    }
+                    resources = Some(map.next_value_seed(ComponentVecDeserializer {
+                        registry: self.property_type_registry,
+                    })?);
+                }
+                SceneField::Entities => {
+                    if entities.is_some() {
+                        return Err(Error::duplicate_field(SCENE_FIELD_ENTITIES));
+                    }
+                    entities = Some(map.next_value_seed(SceneEntitiesDeserializer {
+                        property_type_registry: self.property_type_registry,
+                    })?);
+                }
+                SceneField::Metadata => {
+                    if metadata.is_some() {
+                        return Err(Error::duplicate_field(SCENE_FIELD_METADATA));
+                    }
+                    metadata = Some(map.next_value::<SceneMetadata>()?);
+                }
+            }
+        }
+
+        let entities = entities.ok_or_else(|| Error::missing_field(SCENE_FIELD_ENTITIES))?;
+        Ok(DynamicScene {
+            entities,
+            resources: resources.unwrap_or_default(),
+            metadata,
+        })
+    }
+}
+
+pub struct SceneEntitiesDeserializer<'a> {
+    pub property_type_registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for SceneEntitiesDeserializer<'a> {
+    type Value = Vec<Entity>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SceneEntitiesVisiter {
+            property_type_registry: self.property_type_registry,
+        })
+    }
+}
+
+struct SceneEntitiesVisiter<'a> {
     pub property_type_registry: &'a PropertyTypeRegistry,
 }
 
-impl<'a, 'de> Visitor<'de> for SceneEntitySeqVisiter<'a> {
+impl<'a, 'de> Visitor<'de> for SceneEntitiesVisiter<'a> {
     type Value = Vec<Entity>;
 
     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("list of entities")
+        formatter.write_str("list of entities or map of entity ids to components")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entities = Vec::new();
+        let mut entity_ids = HashSet::default();
+        while let Some(key) = map.next_key::<String>()? {
+            let entity = key
+                .parse::<u32>()
+                .map_err(|_| Error::invalid_value(Unexpected::Str(&key), &"an entity id"))?;
+            if !entity_ids.insert(entity) {
+                return Err(Error::custom(format!("duplicate entity id {}", entity)));
+            }
+            let components = map.next_value_seed(MapEntityComponentsDeserializer {
+                registry: self.property_type_registry,
+            })?;
+            entities.push(Entity { entity, components });
+        }
+
+        Ok(entities)
     }
 
     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
@@ -153,11 +550,14 @@ impl<'a, 'de> DeserializeSeed<'de> for SceneEntityDeserializer<'a> {
 enum EntityField {
     Entity,
     Components,
+    Hash,
 }
 
 pub const ENTITY_STRUCT: &str = "Entity";
 pub const ENTITY_FIELD_ENTITY: &str = "entity";
 pub const ENTITY_FIELD_COMPONENTS: &str = "components";
+pub const ENTITY_FIELD_HASH: &str = "hash";
+pub const HASHED_COMPONENTS_STRUCT: &str = "HashedComponents";
 
 #[derive(Debug)]
 struct SceneEntityVisiter<'a> {
@@ -194,6 +594,9 @@ impl<'a, 'de> Visitor<'de> for SceneEntityVisiter<'a> {
                         registry: self.registry,
                     })?);
                 }
+                EntityField::Hash => {
+                    map.next_value::<IgnoredAny>()?;
+                }
             }
         }
 
@@ -211,6 +614,71 @@ impl<'a, 'de> Visitor<'de> for SceneEntityVisiter<'a> {
     }
 }
 
+/// Reads the value of an entity written as a map entry: either its list of components or a
+/// struct holding the list and the entity's hash.
+struct MapEntityComponentsDeserializer<'a> {
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for MapEntityComponentsDeserializer<'a> {
+    type Value = Vec<DynamicProperties>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for MapEntityComponentsDeserializer<'a> {
+    type Value = Vec<DynamicProperties>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("list of components or hashed components struct")
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        ComponentSeqVisiter {
+            registry: self.registry,
+        }
+        .visit_seq(seq)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut components = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                EntityField::Entity => {
+                    return Err(Error::unknown_field(
+                        ENTITY_FIELD_ENTITY,
+                        &[ENTITY_FIELD_COMPONENTS, ENTITY_FIELD_HASH],
+                    ));
+                }
+                EntityField::Components => {
+                    if components.is_some() {
+                        return Err(Error::duplicate_field(ENTITY_FIELD_COMPONENTS));
+                    }
+                    components = Some(map.next_value_seed(ComponentVecDeserializer {
+                        registry: self.registry,
+                    })?);
+                }
+                EntityField::Hash => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        components.ok_or_else(|| Error::missing_field(ENTITY_FIELD_COMPONENTS))
+    }
+}
+
 pub struct ComponentVecDeserializer<'a> {
     pub registry: &'a PropertyTypeRegistry,
 }
@@ -253,3 +721,1073 @@ impl<'a, 'de> Visitor<'de> for ComponentSeqVisiter<'a> {
         Ok(dynamic_properties)
     }
 }
+
+pub const ARCHETYPE_STRUCT: &str = "Archetype";
+pub const ARCHETYPE_FIELD_COMPONENTS: &str = "components";
+pub const ARCHETYPE_FIELD_ENTITIES: &str = "entities";
+
+/// Serializes the registered components of a single archetype as a table: a header listing the
+/// component type names, followed by one `(entity, component, ...)` row per entity. Only
+/// components with named fields (map properties) can be stored this way.
+pub struct ArchetypeSerializer<'a> {
+    pub archetype: &'a Archetype,
+    pub component_registry: &'a ComponentRegistry,
+    pub registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a> Serialize for ArchetypeSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let registrations = self
+            .archetype
+            .types()
+            .iter()
+            .filter_map(|type_info| self.component_registry.get(&type_info.id()))
+            .collect::<Vec<_>>();
+        let type_names = registrations
+            .iter()
+            .map(|registration| {
+                self.registry
+                    .format_type_name(registration.long_name)
+                    .unwrap_or(registration.long_name)
+            })
+            .collect::<Vec<_>>();
+
+        let mut state = serializer.serialize_struct(ARCHETYPE_STRUCT, 2)?;
+        state.serialize_field(ARCHETYPE_FIELD_COMPONENTS, &type_names)?;
+        state.serialize_field(
+            ARCHETYPE_FIELD_ENTITIES,
+            &ArchetypeRowsSerializer {
+                archetype: self.archetype,
+                registrations: &registrations,
+                registry: self.registry,
+            },
+        )?;
+        state.end()
+    }
+}
+
+struct ArchetypeRowsSerializer<'a> {
+    archetype: &'a Archetype,
+    registrations: &'a [&'a ComponentRegistration],
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a> Serialize for ArchetypeRowsSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_seq(Some(self.archetype.len()))?;
+        for (index, entity) in self.archetype.iter_entities().enumerate() {
+            state.serialize_element(&ArchetypeRowSerializer {
+                entity: entity.id(),
+                index,
+                archetype: self.archetype,
+                registrations: self.registrations,
+                registry: self.registry,
+            })?;
+        }
+        state.end()
+    }
+}
+
+struct ArchetypeRowSerializer<'a> {
+    entity: u32,
+    index: usize,
+    archetype: &'a Archetype,
+    registrations: &'a [&'a ComponentRegistration],
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a> Serialize for ArchetypeRowSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_tuple(self.registrations.len() + 1)?;
+        state.serialize_element(&self.entity)?;
+        for registration in self.registrations.iter() {
+            let properties = registration
+                .get_serialized_component(self.archetype, self.index)
+                .map_err(serde::ser::Error::custom)?;
+            if properties.property_type != PropertyType::Map {
+                return Err(serde::ser::Error::custom(format!(
+                    "{} cannot be stored in an archetype table because it does not have named fields",
+                    properties.type_name
+                )));
+            }
+            state.serialize_element(&MapValueSerializer {
+                properties: &properties,
+                registry: self.registry,
+            })?;
+        }
+        state.end()
+    }
+}
+
+/// Reads an archetype table written by [ArchetypeSerializer] back into scene entities.
+pub struct ArchetypeDeserializer<'a> {
+    pub property_type_registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for ArchetypeDeserializer<'a> {
+    type Value = Vec<Entity>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            ARCHETYPE_STRUCT,
+            &[ARCHETYPE_FIELD_COMPONENTS, ARCHETYPE_FIELD_ENTITIES],
+            ArchetypeVisiter {
+                registry: self.property_type_registry,
+            },
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum ArchetypeField {
+    Components,
+    Entities,
+}
+
+struct ArchetypeVisiter<'a> {
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> Visitor<'de> for ArchetypeVisiter<'a> {
+    type Value = Vec<Entity>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("archetype table")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut type_names: Option<Vec<String>> = None;
+        let mut entities = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                ArchetypeField::Components => {
+                    if type_names.is_some() {
+                        return Err(Error::duplicate_field(ARCHETYPE_FIELD_COMPONENTS));
+                    }
+                    type_names = Some(map.next_value()?);
+                }
+                ArchetypeField::Entities => {
+                    if entities.is_some() {
+                        return Err(Error::duplicate_field(ARCHETYPE_FIELD_ENTITIES));
+                    }
+                    let type_names = type_names.as_ref().ok_or_else(|| {
+                        Error::custom("archetype components must be listed before its entities")
+                    })?;
+                    entities = Some(map.next_value_seed(ArchetypeRowsDeserializer {
+                        type_names,
+                        registry: self.registry,
+                    })?);
+                }
+            }
+        }
+
+        entities.ok_or_else(|| Error::missing_field(ARCHETYPE_FIELD_ENTITIES))
+    }
+}
+
+struct ArchetypeRowsDeserializer<'a> {
+    type_names: &'a [String],
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for ArchetypeRowsDeserializer<'a> {
+    type Value = Vec<Entity>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for ArchetypeRowsDeserializer<'a> {
+    type Value = Vec<Entity>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("list of archetype rows")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut entities = Vec::new();
+        while let Some(entity) = seq.next_element_seed(ArchetypeRowDeserializer {
+            type_names: self.type_names,
+            registry: self.registry,
+        })? {
+            entities.push(entity);
+        }
+
+        Ok(entities)
+    }
+}
+
+struct ArchetypeRowDeserializer<'a> {
+    type_names: &'a [String],
+    registry: &'a PropertyTypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for ArchetypeRowDeserializer<'a> {
+    type Value = Entity;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(self.type_names.len() + 1, self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for ArchetypeRowDeserializer<'a> {
+    type Value = Entity;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "an entity id followed by {} components",
+            self.type_names.len()
+        )
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let entity = seq
+            .next_element::<u32>()?
+            .ok_or_else(|| Error::invalid_length(0, &self))?;
+        let mut components = Vec::with_capacity(self.type_names.len());
+        for (index, type_name) in self.type_names.iter().enumerate() {
+            let mut component = seq
+                .next_element_seed(MapPropertyDeserializer::new(self.registry))?
+                .ok_or_else(|| Error::invalid_length(index + 1, &self))?;
+            component.type_name = type_name.clone();
+            components.push(component);
+        }
+
+        Ok(Entity { entity, components })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ArchetypeDeserializer, ArchetypeSerializer, EntitiesSerializer, SceneDeserializer,
+        SceneSerializer, DEFAULT_MAX_LENGTH_HINT,
+    };
+    use crate::{
+        serialize_ron,
+        test_support::{sample_type_registry, SamplePosition, SampleVelocity},
+        DynamicScene, SceneError,
+    };
+    use bevy_ecs::{Entity, FromResources, Resources, World};
+    use bevy_property::{impl_property, Properties, PropertiesVal, Property, PropertyTypeRegistry};
+    use bevy_type_registry::{ComponentRegistry, TypeRegistry};
+    use serde::{de::DeserializeSeed, Deserialize, Serialize};
+    use std::{
+        collections::HashMap,
+        convert::TryFrom,
+        sync::atomic::{AtomicBool, Ordering},
+        time::Duration,
+    };
+
+    #[derive(Property, Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+    #[serde(try_from = "u8", into = "u8")]
+    struct Percent(u8);
+
+    impl TryFrom<u8> for Percent {
+        type Error = String;
+
+        fn try_from(value: u8) -> Result<Self, Self::Error> {
+            if value <= 100 {
+                Ok(Percent(value))
+            } else {
+                Err(format!("{} is not a valid percentage", value))
+            }
+        }
+    }
+
+    impl From<Percent> for u8 {
+        fn from(percent: Percent) -> Self {
+            percent.0
+        }
+    }
+
+    #[derive(Properties, Default)]
+    struct Volume {
+        level: Percent,
+    }
+
+    #[derive(Properties, Default)]
+    struct Offset {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Properties, Default)]
+    struct Body {
+        offset: Offset,
+        mass: f32,
+    }
+
+    #[derive(Properties, Default)]
+    struct Inventory(Vec<u32>);
+
+    #[derive(Properties, Default)]
+    struct Tags(HashMap<String, u32>);
+
+    #[derive(Properties, Default)]
+    struct Heading(f32, f32);
+
+    #[derive(Properties)]
+    struct Parent(Entity);
+
+    impl FromResources for Parent {
+        fn from_resources(_resources: &Resources) -> Self {
+            Parent(Entity::new(u32::MAX))
+        }
+    }
+
+    #[derive(Properties, Default)]
+    struct Speed(f32);
+
+    #[derive(Properties, Default)]
+    struct Player;
+
+    #[derive(Properties, Default)]
+    struct Health {
+        current: f32,
+        max: f32,
+    }
+
+    #[derive(Properties, Default)]
+    struct Cooldown {
+        duration: Duration,
+        remaining: Duration,
+    }
+
+    mod hex {
+        use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(value: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&format!("{:08x}", value))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+            let hex = String::deserialize(deserializer)?;
+            u32::from_str_radix(&hex, 16).map_err(D::Error::custom)
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+    struct HexColor {
+        #[serde(with = "hex")]
+        rgba: u32,
+    }
+
+    impl_property!(HexColor);
+
+    #[derive(Properties, Default)]
+    struct Tint {
+        color: HexColor,
+    }
+
+    #[derive(Properties, Default)]
+    struct Indices {
+        values: Box<[u32]>,
+        weights: Vec<f32>,
+    }
+
+    fn registries() -> (ComponentRegistry, PropertyTypeRegistry) {
+        let mut component_registry = ComponentRegistry::default();
+        component_registry.register::<Volume>();
+        let mut property_type_registry = PropertyTypeRegistry::default();
+        property_type_registry.register::<Percent>();
+        (component_registry, property_type_registry)
+    }
+
+    fn deserialize_ron(
+        ron: &str,
+        property_type_registry: &PropertyTypeRegistry,
+    ) -> Result<DynamicScene, ron::Error> {
+        let mut deserializer = ron::de::Deserializer::from_str(ron)?;
+        SceneDeserializer {
+            property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+    }
+
+    #[test]
+    fn custom_deserialize_round_trip() {
+        let (component_registry, property_type_registry) = registries();
+        let mut world = World::default();
+        world.spawn((Volume { level: Percent(40) },));
+
+        let ron = DynamicScene::from_world(&world, &component_registry)
+            .serialize_ron(&property_type_registry)
+            .unwrap();
+        let scene = deserialize_ron(&ron, &property_type_registry).unwrap();
+        let level = scene.entities[0].components[0]
+            .prop_val::<Percent>("level")
+            .unwrap();
+        assert_eq!(*level, Percent(40));
+    }
+
+    #[test]
+    fn custom_deserialize_error() {
+        let (_, property_type_registry) = registries();
+        let ron = r#"[
+  (
+    entity: 0,
+    components: [
+      {
+        "type": "Volume",
+        "map": {
+          "level": {
+            "type": "Percent",
+            "value": 150,
+          },
+        },
+      },
+    ],
+  ),
+]"#;
+        let error = deserialize_ron(ron, &property_type_registry).unwrap_err();
+        assert!(error.to_string().contains("150 is not a valid percentage"));
+    }
+
+    #[test]
+    fn serialize_progress() {
+        let (component_registry, property_type_registry) = registries();
+        let mut world = World::default();
+        for level in 0..3 {
+            world.spawn((Volume {
+                level: Percent(level),
+            },));
+        }
+
+        let scene = DynamicScene::from_world(&world, &component_registry);
+        let mut calls = Vec::new();
+        serialize_ron(
+            SceneSerializer::new(&scene, &property_type_registry)
+                .with_progress(|done, total| calls.push((done, total))),
+        )
+        .unwrap();
+        assert_eq!(calls, vec![(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn serialize_cancelled() {
+        let (component_registry, property_type_registry) = registries();
+        let mut world = World::default();
+        for level in 0..3 {
+            world.spawn((Volume {
+                level: Percent(level),
+            },));
+        }
+
+        let scene = DynamicScene::from_world(&world, &component_registry);
+        let cancel = AtomicBool::new(false);
+        let mut serialized = 0;
+        let result = SceneSerializer::new(&scene, &property_type_registry)
+            .with_progress(|done, _total| {
+                serialized = done;
+                cancel.store(true, Ordering::Relaxed);
+            })
+            .with_cancel_flag(&cancel)
+            .serialize_ron();
+        assert!(matches!(result, Err(SceneError::Cancelled)));
+        assert_eq!(serialized, 1);
+    }
+
+    #[test]
+    fn length_hint_limit() {
+        let (component_registry, property_type_registry) = registries();
+        let mut world = World::default();
+        for level in 0..3 {
+            world.spawn((Volume {
+                level: Percent(level),
+            },));
+        }
+        let scene = DynamicScene::from_world(&world, &component_registry);
+
+        let entities = |max_length_hint| EntitiesSerializer {
+            entities: &scene.entities,
+            registry: &property_type_registry,
+            progress: None,
+            cancel: None,
+            as_map: false,
+            entity_hashes: false,
+            max_length_hint,
+        };
+        assert_eq!(entities(DEFAULT_MAX_LENGTH_HINT).length_hint(), Some(3));
+        assert_eq!(entities(3).length_hint(), Some(3));
+        assert_eq!(entities(2).length_hint(), None);
+
+        let unbounded =
+            serialize_ron(SceneSerializer::new(&scene, &property_type_registry)).unwrap();
+        let limited = serialize_ron(
+            SceneSerializer::new(&scene, &property_type_registry).with_max_length_hint(2),
+        )
+        .unwrap();
+        assert_eq!(limited, unbounded);
+        let json = serde_json::to_string(
+            &SceneSerializer::new(&scene, &property_type_registry).with_max_length_hint(2),
+        )
+        .unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let loaded = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+        assert_eq!(loaded.entities.len(), 3);
+    }
+
+    #[test]
+    fn entities_as_map_round_trip() {
+        let (component_registry, property_type_registry) = registries();
+        let mut world = World::default();
+        for level in 0..3 {
+            world.spawn((Volume {
+                level: Percent(level * 10),
+            },));
+        }
+
+        let scene = DynamicScene::from_world(&world, &component_registry);
+        let json = serde_json::to_string(
+            &SceneSerializer::new(&scene, &property_type_registry).with_entities_as_map(),
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value["entities"].is_object());
+
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let loaded = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+        assert_eq!(loaded.entities.len(), 3);
+        for entity in scene.entities.iter() {
+            let loaded_entity = loaded
+                .entities
+                .iter()
+                .find(|loaded_entity| loaded_entity.entity == entity.entity)
+                .unwrap();
+            assert_eq!(
+                loaded_entity.components[0].prop_val::<Percent>("level"),
+                entity.components[0].prop_val::<Percent>("level")
+            );
+        }
+    }
+
+    #[test]
+    fn entities_as_map_with_hashes() {
+        let (component_registry, property_type_registry) = registries();
+        let mut world = World::default();
+        let entity = world.spawn((Volume { level: Percent(20) },));
+
+        let scene = DynamicScene::from_world(&world, &component_registry);
+        let json = serde_json::to_string(
+            &SceneSerializer::new(&scene, &property_type_registry)
+                .with_entities_as_map()
+                .with_entity_hashes(),
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let hash = scene.entities[0]
+            .component_hash(&property_type_registry)
+            .unwrap();
+        assert_eq!(
+            value["entities"][entity.id().to_string()]["hash"].as_u64(),
+            Some(hash)
+        );
+
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let loaded = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+        assert_eq!(loaded.entities.len(), 1);
+        assert_eq!(loaded.entities[0].entity, entity.id());
+        assert_eq!(
+            loaded.entities[0].components[0].prop_val::<Percent>("level"),
+            scene.entities[0].components[0].prop_val::<Percent>("level")
+        );
+    }
+
+    #[test]
+    fn entities_as_map_duplicate_id() {
+        let (_, property_type_registry) = registries();
+        let json = r#"{"entities": {"1": [], "1": []}}"#;
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let result = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer);
+        assert!(result
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("duplicate entity id 1"));
+    }
+
+    #[test]
+    fn archetype_table_round_trip() {
+        let type_registry = sample_type_registry();
+        let mut world = World::default();
+        for i in 0..4 {
+            world.spawn((
+                SamplePosition {
+                    x: i as f32,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                SampleVelocity {
+                    x: 1.0,
+                    y: -1.0,
+                    z: 0.0,
+                },
+            ));
+        }
+
+        let component_registry = type_registry.component.read();
+        let property_type_registry = type_registry.property.read();
+        let archetype = world
+            .archetypes()
+            .find(|archetype| archetype.has::<SamplePosition>())
+            .unwrap();
+        let ron = serialize_ron(ArchetypeSerializer {
+            archetype,
+            component_registry: &component_registry,
+            registry: &property_type_registry,
+        })
+        .unwrap();
+
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let entities = ArchetypeDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+        assert_eq!(entities.len(), 4);
+        assert!(entities.iter().all(|entity| entity.components.len() == 2));
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+        let scene = DynamicScene {
+            entities,
+            resources: Vec::new(),
+            metadata: None,
+        };
+        let mut loaded_world = World::default();
+        scene.write_to_world(&mut loaded_world, &resources).unwrap();
+        let mut positions = loaded_world
+            .query::<(&SamplePosition, &SampleVelocity)>()
+            .map(|(position, velocity)| (position.x, velocity.y))
+            .collect::<Vec<_>>();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            positions,
+            vec![(0.0, -1.0), (1.0, -1.0), (2.0, -1.0), (3.0, -1.0)]
+        );
+    }
+
+    #[test]
+    fn nested_properties_round_trip() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().register::<Body>();
+        let mut world = World::default();
+        world.spawn((Body {
+            offset: Offset { x: 1.5, y: -2.0 },
+            mass: 10.0,
+        },));
+        let scene = DynamicScene::from_world(&world, &type_registry.component.read());
+        let property_type_registry = type_registry.property.read();
+
+        let ron = scene.serialize_ron(&property_type_registry).unwrap();
+        let from_ron = deserialize_ron(&ron, &property_type_registry).unwrap();
+        let json =
+            serde_json::to_string(&SceneSerializer::new(&scene, &property_type_registry)).unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let from_json = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+        for loaded_scene in [from_ron, from_json].iter() {
+            let mut loaded_world = World::default();
+            loaded_scene
+                .write_to_world(&mut loaded_world, &resources)
+                .unwrap();
+            let body = loaded_world.query::<&Body>().next().unwrap();
+            assert_eq!((body.offset.x, body.offset.y, body.mass), (1.5, -2.0, 10.0));
+        }
+    }
+
+    #[test]
+    fn seq_and_map_component_round_trip() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().register::<Inventory>();
+        type_registry.component.write().register::<Tags>();
+        type_registry
+            .property
+            .write()
+            .register::<HashMap<String, u32>>();
+        let mut tags = HashMap::new();
+        tags.insert("enemy".to_string(), 2);
+        tags.insert("boss".to_string(), 1);
+        let mut world = World::default();
+        world.spawn((Inventory(vec![3, 1, 4]), Tags(tags.clone())));
+
+        let ron = {
+            let component_registry = type_registry.component.read();
+            let property_type_registry = type_registry.property.read();
+            DynamicScene::from_world(&world, &component_registry)
+                .serialize_ron(&property_type_registry)
+                .unwrap()
+        };
+        let scene = deserialize_ron(&ron, &type_registry.property.read()).unwrap();
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut loaded_world = World::default();
+        scene.write_to_world(&mut loaded_world, &resources).unwrap();
+        let (inventory, loaded_tags) = loaded_world.query::<(&Inventory, &Tags)>().next().unwrap();
+        assert_eq!(inventory.0, vec![3, 1, 4]);
+        assert_eq!(loaded_tags.0, tags);
+    }
+
+    #[test]
+    fn components_in_any_order() {
+        let type_registry = sample_type_registry();
+        let position = format!(
+            r#"{{"type": "{}", "map": {{"x": 1.0, "y": 2.0}}}}"#,
+            std::any::type_name::<SamplePosition>()
+        );
+        let velocity = format!(
+            r#"{{"type": "{}", "map": {{"y": 4.0, "x": 3.0}}}}"#,
+            std::any::type_name::<SampleVelocity>()
+        );
+        let load = |ron: String| {
+            let scene = deserialize_ron(&ron, &type_registry.property.read()).unwrap();
+            let mut resources = Resources::default();
+            resources.insert(type_registry.clone());
+            let mut world = World::default();
+            scene.write_to_world(&mut world, &resources).unwrap();
+            world
+                .query::<(&SamplePosition, &SampleVelocity)>()
+                .map(|(position, velocity)| (position.x, position.y, velocity.x, velocity.y))
+                .collect::<Vec<_>>()
+        };
+
+        let ordered = format!("[(entity: 0, components: [{}, {}])]", position, velocity);
+        let shuffled = format!("[(components: [{}, {}], entity: 0)]", velocity, position);
+        assert_eq!(load(ordered), vec![(1.0, 2.0, 3.0, 4.0)]);
+        assert_eq!(load(shuffled), vec![(1.0, 2.0, 3.0, 4.0)]);
+    }
+
+    #[test]
+    fn component_filter() {
+        let type_registry = sample_type_registry();
+        let mut world = World::default();
+        world.spawn((
+            SamplePosition {
+                x: 1.0,
+                y: 2.0,
+                z: 0.0,
+            },
+            SampleVelocity {
+                x: 3.0,
+                y: 4.0,
+                z: 0.0,
+            },
+        ));
+        world.spawn((SampleVelocity {
+            x: 5.0,
+            y: 6.0,
+            z: 0.0,
+        },));
+        let ron = DynamicScene::from_world(&world, &type_registry.component.read())
+            .serialize_ron(&type_registry.property.read())
+            .unwrap();
+
+        let property_type_registry = type_registry.property.read();
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let scene = SceneDeserializer {
+            property_type_registry: &property_type_registry,
+        }
+        .with_component_filter(|type_name| type_name == std::any::type_name::<SamplePosition>())
+        .deserialize(&mut deserializer)
+        .unwrap();
+        assert_eq!(scene.entities.len(), 2);
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+        let mut loaded_world = World::default();
+        scene.write_to_world(&mut loaded_world, &resources).unwrap();
+        assert_eq!(loaded_world.query::<&SamplePosition>().count(), 1);
+        assert_eq!(loaded_world.query::<&SampleVelocity>().count(), 0);
+    }
+
+    #[test]
+    fn tuple_and_unit_struct_round_trip() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().register::<Heading>();
+        type_registry.component.write().register::<Speed>();
+        type_registry.component.write().register::<Player>();
+        let mut world = World::default();
+        world.spawn((Heading(0.6, 0.8), Speed(2.5), Player));
+        world.spawn((Speed(1.0),));
+
+        let ron = DynamicScene::from_world(&world, &type_registry.component.read())
+            .serialize_ron(&type_registry.property.read())
+            .unwrap();
+        let scene = deserialize_ron(&ron, &type_registry.property.read()).unwrap();
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut loaded_world = World::default();
+        scene.write_to_world(&mut loaded_world, &resources).unwrap();
+        let (heading, speed, _player) = loaded_world
+            .query::<(&Heading, &Speed, &Player)>()
+            .next()
+            .unwrap();
+        assert_eq!((heading.0, heading.1, speed.0), (0.6, 0.8, 2.5));
+        assert_eq!(loaded_world.query::<&Player>().count(), 1);
+        let mut speeds = loaded_world
+            .query::<&Speed>()
+            .map(|speed| speed.0)
+            .collect::<Vec<_>>();
+        speeds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(speeds, vec![1.0, 2.5]);
+    }
+
+    #[test]
+    fn duration_round_trip() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().register::<Cooldown>();
+        type_registry.property.write().register::<Duration>();
+        let mut world = World::default();
+        world.spawn((Cooldown {
+            duration: Duration::from_secs(3),
+            remaining: Duration::new(1, 999_999_999),
+        },));
+
+        let ron = DynamicScene::from_world(&world, &type_registry.component.read())
+            .serialize_ron(&type_registry.property.read())
+            .unwrap();
+        let scene = deserialize_ron(&ron, &type_registry.property.read()).unwrap();
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut loaded_world = World::default();
+        scene.write_to_world(&mut loaded_world, &resources).unwrap();
+        let cooldown = loaded_world.query::<&Cooldown>().next().unwrap();
+        assert_eq!(cooldown.duration, Duration::from_secs(3));
+        // durations are stored as whole seconds and nanoseconds, so nothing is rounded
+        assert_eq!(cooldown.remaining, Duration::new(1, 999_999_999));
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().register::<Health>();
+        // written by a version of Health that also had a `regen` field
+        let ron = format!(
+            r#"[(entity: 0, components: [{{"type": "{}", "map": {{"current": 40.0, "max": 100.0,
+                "regen": 0.5}}}}])]"#,
+            std::any::type_name::<Health>()
+        );
+        let scene = deserialize_ron(&ron, &type_registry.property.read()).unwrap();
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut world = World::default();
+        scene.write_to_world(&mut world, &resources).unwrap();
+        let health = world.query::<&Health>().next().unwrap();
+        assert_eq!((health.current, health.max), (40.0, 100.0));
+    }
+
+    #[test]
+    fn entity_hashes() {
+        let type_registry = sample_type_registry();
+        let mut world = World::default();
+        let entities = (0..3)
+            .map(|i| {
+                world.spawn((SamplePosition {
+                    x: i as f32,
+                    y: 0.0,
+                    z: 0.0,
+                },))
+            })
+            .collect::<Vec<_>>();
+        let component_registry = type_registry.component.read();
+        let property_type_registry = type_registry.property.read();
+        let hashes = |world: &World| {
+            DynamicScene::from_world(world, &component_registry)
+                .entities
+                .iter()
+                .map(|entity| {
+                    let hash = entity.component_hash(&property_type_registry).unwrap();
+                    (entity.entity, hash)
+                })
+                .collect::<HashMap<_, _>>()
+        };
+
+        let before = hashes(&world);
+        world.get_mut::<SamplePosition>(entities[1]).unwrap().y = 5.0;
+        let after = hashes(&world);
+        assert_eq!(before[&entities[0].id()], after[&entities[0].id()]);
+        assert_ne!(before[&entities[1].id()], after[&entities[1].id()]);
+        assert_eq!(before[&entities[2].id()], after[&entities[2].id()]);
+
+        let scene = DynamicScene::from_world(&world, &component_registry);
+        let ron = serialize_ron(
+            SceneSerializer::new(&scene, &property_type_registry).with_entity_hashes(),
+        )
+        .unwrap();
+        assert!(ron.contains(&format!("hash: {}", after[&entities[1].id()])));
+        let loaded = deserialize_ron(&ron, &property_type_registry).unwrap();
+        assert_eq!(
+            loaded.content_hash(&property_type_registry).unwrap(),
+            scene.content_hash(&property_type_registry).unwrap()
+        );
+    }
+
+    fn tint_type_registry() -> TypeRegistry {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().register::<Tint>();
+        type_registry.property.write().register::<HexColor>();
+        type_registry
+    }
+
+    #[test]
+    fn serde_with_module_serialize() {
+        let type_registry = tint_type_registry();
+        let mut world = World::default();
+        world.spawn((Tint {
+            color: HexColor { rgba: 0xff80_00ff },
+        },));
+
+        let ron = DynamicScene::from_world(&world, &type_registry.component.read())
+            .serialize_ron(&type_registry.property.read())
+            .unwrap();
+        assert!(ron.contains("rgba: \"ff8000ff\""));
+    }
+
+    #[test]
+    fn serde_with_module_deserialize() {
+        let type_registry = tint_type_registry();
+        let ron = format!(
+            r#"[(entity: 0, components: [{{"type": "{}", "map": {{"color": {{"type": "{}",
+                "value": (rgba: "00ff00ff")}}}}}}])]"#,
+            std::any::type_name::<Tint>(),
+            std::any::type_name::<HexColor>()
+        );
+        let scene = deserialize_ron(&ron, &type_registry.property.read()).unwrap();
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut world = World::default();
+        scene.write_to_world(&mut world, &resources).unwrap();
+        let tint = world.query::<&Tint>().next().unwrap();
+        assert_eq!(tint.color, HexColor { rgba: 0x00ff_00ff });
+
+        let invalid = ron.replace("00ff00ff", "not hex");
+        let type_registry = tint_type_registry();
+        assert!(deserialize_ron(&invalid, &type_registry.property.read()).is_err());
+    }
+
+    fn round_trip_indices(indices: Indices) -> Indices {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().register::<Indices>();
+        let mut world = World::default();
+        world.spawn((indices,));
+
+        let ron = DynamicScene::from_world(&world, &type_registry.component.read())
+            .serialize_ron(&type_registry.property.read())
+            .unwrap();
+        let scene = deserialize_ron(&ron, &type_registry.property.read()).unwrap();
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut loaded_world = World::default();
+        scene.write_to_world(&mut loaded_world, &resources).unwrap();
+        let mut loaded = loaded_world.query_mut::<&mut Indices>().next().unwrap();
+        std::mem::take(&mut *loaded)
+    }
+
+    #[test]
+    fn boxed_slice_round_trip() {
+        let indices = round_trip_indices(Indices {
+            values: vec![3, 1, 2].into_boxed_slice(),
+            weights: vec![0.5, 0.25],
+        });
+        assert_eq!(&*indices.values, &[3, 1, 2]);
+        assert_eq!(indices.weights, vec![0.5, 0.25]);
+    }
+
+    #[test]
+    fn empty_boxed_slice_round_trip() {
+        let indices = round_trip_indices(Indices::default());
+        assert!(indices.values.is_empty());
+        assert!(indices.weights.is_empty());
+    }
+
+    #[test]
+    fn entity_references_are_stored_ids() {
+        let type_registry = TypeRegistry::default();
+        type_registry.component.write().register::<Parent>();
+        let mut world = World::default();
+        // recycle the parent's id so its handle has a non-zero generation
+        let despawned = world.spawn(());
+        world.despawn(despawned).unwrap();
+        let parent = world.spawn(());
+        assert_eq!(parent.id(), despawned.id());
+        assert_ne!(parent, Entity::new(parent.id()));
+        world.spawn((Parent(parent),));
+
+        let ron = DynamicScene::from_world(&world, &type_registry.component.read())
+            .serialize_ron(&type_registry.property.read())
+            .unwrap();
+        assert!(!ron.contains(&parent.to_bits().to_string()));
+        let scene = deserialize_ron(&ron, &type_registry.property.read()).unwrap();
+        let child = scene
+            .entities
+            .iter()
+            .find(|entity| !entity.components.is_empty())
+            .unwrap();
+        let stored = child.components[0].prop_with_index(0).unwrap();
+        assert_eq!(
+            *stored.any().downcast_ref::<Entity>().unwrap(),
+            Entity::new(parent.id())
+        );
+        assert!(scene
+            .entities
+            .iter()
+            .any(|entity| entity.entity == parent.id()));
+    }
+}
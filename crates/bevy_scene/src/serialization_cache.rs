@@ -0,0 +1,153 @@
+use crate::{serde::EntitySerializer, Entity};
+use bevy_ecs::{Archetype, Entity as WorldEntity, World};
+use bevy_property::PropertyTypeRegistry;
+use bevy_type_registry::ComponentRegistry;
+use bevy_utils::HashMap;
+
+struct CachedEntity {
+    archetype: usize,
+    ron: String,
+}
+
+/// Re-serializes a world to RON while reusing the output of entities that haven't changed since
+/// the previous call. An entity is serialized again when any of its components was added or
+/// mutated, or when it moved to a different archetype. Changes are read from the world's
+/// trackers, so call [SceneSerializationCache::serialize_ron] at least once between calls to
+/// `World::clear_trackers`.
+#[derive(Default)]
+pub struct SceneSerializationCache {
+    entities: HashMap<WorldEntity, CachedEntity>,
+    reused: usize,
+}
+
+impl SceneSerializationCache {
+    /// Serializes every entity of `world` as a list of entities, the same layout an entity-only
+    /// [DynamicScene](crate::DynamicScene) is saved with.
+    pub fn serialize_ron(
+        &mut self,
+        world: &World,
+        component_registry: &ComponentRegistry,
+        registry: &PropertyTypeRegistry,
+    ) -> Result<String, ron::Error> {
+        let mut entities = HashMap::default();
+        let mut output = String::from("[");
+        self.reused = 0;
+        for (archetype_index, archetype) in world.archetypes().enumerate() {
+            for (index, entity) in archetype.iter_entities().enumerate() {
+                let cached = self
+                    .entities
+                    .remove(entity)
+                    .filter(|cached| cached.archetype == archetype_index)
+                    .filter(|_| !is_changed(archetype, index));
+                let cached = match cached {
+                    Some(cached) => {
+                        self.reused += 1;
+                        cached
+                    }
+                    None => CachedEntity {
+                        archetype: archetype_index,
+                        ron: serialize_entity(
+                            archetype,
+                            index,
+                            *entity,
+                            component_registry,
+                            registry,
+                        )?,
+                    },
+                };
+                output.push_str(&cached.ron);
+                output.push(',');
+                entities.insert(*entity, cached);
+            }
+        }
+        output.push(']');
+        self.entities = entities;
+        Ok(output)
+    }
+
+    /// The number of entities whose previous output was reused by the last call to
+    /// [SceneSerializationCache::serialize_ron].
+    pub fn reused(&self) -> usize {
+        self.reused
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+}
+
+fn is_changed(archetype: &Archetype, index: usize) -> bool {
+    archetype.types().iter().any(|type_info| {
+        let state = archetype.get_type_state(type_info.id()).unwrap();
+        // SAFETY: `index` is below the archetype's length, and the trackers hold one flag per row
+        unsafe { *state.mutated().as_ptr().add(index) || *state.added().as_ptr().add(index) }
+    })
+}
+
+fn serialize_entity(
+    archetype: &Archetype,
+    index: usize,
+    entity: WorldEntity,
+    component_registry: &ComponentRegistry,
+    registry: &PropertyTypeRegistry,
+) -> Result<String, ron::Error> {
+    let entity = Entity {
+        entity: entity.id(),
+        components: archetype
+            .types()
+            .iter()
+            .filter_map(|type_info| component_registry.get(&type_info.id()))
+            .map(|registration| registration.get_serialized_component(archetype, index))
+            .collect::<Result<_, _>>()
+            .map_err(serde::ser::Error::custom)?,
+    };
+    ron::ser::to_string(&EntitySerializer {
+        entity: &entity,
+        registry,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SceneSerializationCache;
+    use crate::{
+        serde::SceneDeserializer,
+        test_support::{build_sample_world, SamplePosition},
+        DynamicScene,
+    };
+    use bevy_ecs::Entity;
+    use bevy_property::PropertyTypeRegistry;
+    use serde::de::DeserializeSeed;
+
+    #[test]
+    fn reuses_unchanged_entities() {
+        let (mut world, component_registry) = build_sample_world(0, 10);
+        let registry = PropertyTypeRegistry::default();
+        let mut cache = SceneSerializationCache::default();
+
+        cache
+            .serialize_ron(&world, &component_registry, &registry)
+            .unwrap();
+        assert_eq!(cache.reused(), 0);
+        world.clear_trackers();
+
+        let entity = world.query::<Entity>().next().unwrap();
+        world.get_mut::<SamplePosition>(entity).unwrap().x = 100.0;
+        let ron = cache
+            .serialize_ron(&world, &component_registry, &registry)
+            .unwrap();
+        assert_eq!(cache.reused(), 9);
+
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let cached_scene = SceneDeserializer {
+            property_type_registry: &registry,
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+        let full_scene = DynamicScene::from_world(&world, &component_registry);
+        assert_eq!(
+            cached_scene.content_hash(&registry).unwrap(),
+            full_scene.content_hash(&registry).unwrap()
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{serde::SceneDeserializer, DynamicScene, DynamicSceneToWorldError, Entity};
+use bevy_ecs::{Resources, World};
+use bevy_property::DynamicProperties;
+use serde::de::DeserializeSeed;
+
+/// A deferred spawn of one scene entity: its stored id and its components as dynamic values.
+/// Commands can be inspected or transformed before they are applied with
+/// [apply_spawn_commands].
+#[derive(Debug)]
+pub struct SpawnCommand {
+    pub entity: u32,
+    pub components: Vec<DynamicProperties>,
+}
+
+impl<'a> SceneDeserializer<'a> {
+    /// Parses a scene into one [SpawnCommand] per entity instead of a [DynamicScene], so applying
+    /// it to a world can happen later. Scene resources aren't spawned and are dropped.
+    pub fn into_commands<'de, D>(self, deserializer: D) -> Result<Vec<SpawnCommand>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let scene = self.deserialize(deserializer)?;
+        Ok(scene
+            .entities
+            .into_iter()
+            .map(|entity| SpawnCommand {
+                entity: entity.entity,
+                components: entity.components,
+            })
+            .collect())
+    }
+}
+
+/// Spawns the entities described by `commands` into `world`, looking components up in the
+/// [TypeRegistry](bevy_type_registry::TypeRegistry) resource. Entity references between the
+/// commands are mapped the same way as when writing a [DynamicScene] to a world.
+pub fn apply_spawn_commands(
+    commands: Vec<SpawnCommand>,
+    world: &mut World,
+    resources: &Resources,
+) -> Result<(), DynamicSceneToWorldError> {
+    DynamicScene {
+        entities: commands
+            .into_iter()
+            .map(|command| Entity {
+                entity: command.entity,
+                components: command.components,
+            })
+            .collect(),
+        resources: Vec::new(),
+        metadata: None,
+    }
+    .write_to_world(world, resources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::apply_spawn_commands;
+    use crate::{
+        serde::SceneDeserializer,
+        test_support::{sample_type_registry, SamplePosition},
+        DynamicScene,
+    };
+    use bevy_ecs::{Resources, World};
+
+    #[test]
+    fn into_commands() {
+        let type_registry = sample_type_registry();
+        let mut world = World::default();
+        for i in 0..3 {
+            world.spawn((SamplePosition {
+                x: i as f32,
+                y: 0.0,
+                z: 0.0,
+            },));
+        }
+        let ron = DynamicScene::from_world(&world, &type_registry.component.read())
+            .serialize_ron(&type_registry.property.read())
+            .unwrap();
+
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let mut commands = SceneDeserializer {
+            property_type_registry: &type_registry.property.read(),
+        }
+        .into_commands(&mut deserializer)
+        .unwrap();
+        assert_eq!(commands.len(), 3);
+        assert!(commands.iter().all(|command| command.components.len() == 1));
+        // nothing is spawned until the commands are applied
+        commands.retain(|command| command.entity != 1);
+
+        let mut resources = Resources::default();
+        resources.insert(type_registry);
+        let mut loaded_world = World::default();
+        apply_spawn_commands(commands, &mut loaded_world, &resources).unwrap();
+        let mut xs = loaded_world
+            .query::<&SamplePosition>()
+            .map(|position| position.x)
+            .collect::<Vec<_>>();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(xs, vec![0.0, 2.0]);
+    }
+}
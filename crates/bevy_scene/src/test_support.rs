@@ -0,0 +1,162 @@
+//! Deterministic worlds for testing scene serialization, shared by this crate's tests and the
+//! scene benchmarks. Downstream crates can use them by enabling the `test-support` feature.
+
+use crate::DynamicScene;
+use bevy_ecs::{Entity, EntityMap, FromResources, MapEntities, MapEntitiesError, Resources, World};
+use bevy_property::Properties;
+use bevy_type_registry::{ComponentRegistration, ComponentRegistry, TypeRegistry};
+
+#[derive(Properties, Default, Debug, Clone, PartialEq)]
+pub struct SamplePosition {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[derive(Properties, Default, Debug, Clone, PartialEq)]
+pub struct SampleVelocity {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[derive(Properties, Default, Debug, Clone, PartialEq)]
+pub struct SampleName {
+    pub value: String,
+}
+
+/// An entity reference that is remapped when a scene is written to a world. It isn't part of
+/// the sample world; register it with [register_sample_parent].
+#[derive(Properties, Debug, Clone, PartialEq)]
+pub struct SampleParent(pub Entity);
+
+impl FromResources for SampleParent {
+    fn from_resources(_resources: &Resources) -> Self {
+        SampleParent(Entity::new(u32::MAX))
+    }
+}
+
+impl MapEntities for SampleParent {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
+        self.0 = entity_map.get(self.0)?;
+        Ok(())
+    }
+}
+
+/// Registers [SampleParent] so that its entity is mapped when scenes are written.
+pub fn register_sample_parent(component_registry: &mut ComponentRegistry) {
+    component_registry.add_registration(
+        ComponentRegistration::build::<SampleParent>()
+            .map_entities()
+            .finish(),
+    );
+}
+
+/// Registers the sample component types with `component_registry`.
+pub fn register_sample_components(component_registry: &mut ComponentRegistry) {
+    component_registry.register::<SamplePosition>();
+    component_registry.register::<SampleVelocity>();
+    component_registry.register::<SampleName>();
+}
+
+/// Returns a [TypeRegistry] with the sample component types registered, for tests that load
+/// sample scenes into a world.
+pub fn sample_type_registry() -> TypeRegistry {
+    let type_registry = TypeRegistry::default();
+    register_sample_components(&mut type_registry.component.write());
+    type_registry
+}
+
+/// Builds a world of `entity_count` entities. Every entity has a [SamplePosition], every second
+/// entity a [SampleVelocity] and every third entity a [SampleName]. Component values are drawn
+/// from a generator seeded with `seed`, so the same seed and count always produce the same world.
+pub fn build_sample_world(seed: u64, entity_count: usize) -> (World, ComponentRegistry) {
+    let mut component_registry = ComponentRegistry::default();
+    register_sample_components(&mut component_registry);
+
+    let mut rng = SampleRng::new(seed);
+    let mut world = World::default();
+    for index in 0..entity_count {
+        let entity = world.spawn((SamplePosition {
+            x: rng.next_f32(),
+            y: rng.next_f32(),
+            z: rng.next_f32(),
+        },));
+        if index % 2 == 0 {
+            let velocity = SampleVelocity {
+                x: rng.next_f32(),
+                y: rng.next_f32(),
+                z: rng.next_f32(),
+            };
+            world.insert_one(entity, velocity).unwrap();
+        }
+        if index % 3 == 0 {
+            let name = SampleName {
+                value: format!("entity {}", rng.next_u64() % 1000),
+            };
+            world.insert_one(entity, name).unwrap();
+        }
+    }
+
+    (world, component_registry)
+}
+
+/// Builds a sample world with [build_sample_world] and extracts it as a [DynamicScene].
+pub fn build_sample_scene(seed: u64, entity_count: usize) -> DynamicScene {
+    let (world, component_registry) = build_sample_world(seed, entity_count);
+    DynamicScene::from_world(&world, &component_registry)
+}
+
+/// A small xorshift generator, which keeps sample worlds reproducible across platforms and
+/// without extra dependencies.
+struct SampleRng(u64);
+
+impl SampleRng {
+    fn new(seed: u64) -> Self {
+        // xorshift gets stuck at zero
+        SampleRng(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a value in `-100.0..100.0`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32 * 200.0 - 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_sample_scene, SamplePosition};
+    use bevy_property::PropertyTypeRegistry;
+
+    #[test]
+    fn same_seed_same_scene() {
+        let registry = PropertyTypeRegistry::default();
+        let first = build_sample_scene(7, 20);
+        let second = build_sample_scene(7, 20);
+        assert_eq!(first.entities.len(), 20);
+        assert_eq!(
+            first.serialize_ron(&registry).unwrap(),
+            second.serialize_ron(&registry).unwrap()
+        );
+
+        let other = build_sample_scene(8, 20);
+        assert_ne!(
+            first.serialize_ron(&registry).unwrap(),
+            other.serialize_ron(&registry).unwrap()
+        );
+        let positions = first
+            .entities
+            .iter()
+            .flat_map(|entity| entity.components.iter())
+            .filter(|component| component.type_name == std::any::type_name::<SamplePosition>())
+            .count();
+        assert_eq!(positions, 20);
+    }
+}
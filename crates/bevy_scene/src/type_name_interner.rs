@@ -0,0 +1,116 @@
+use crate::DynamicScene;
+use bevy_property::PropertyTypeRegistry;
+use std::{collections::HashSet, sync::Arc};
+
+/// Stores a single shared copy of each type name. Reusing one interner across many scenes keeps a
+/// batch serialization pipeline from allocating the same names over and over.
+#[derive(Debug, Default)]
+pub struct TypeNameInterner {
+    names: HashSet<Arc<str>>,
+}
+
+impl TypeNameInterner {
+    pub fn intern(&mut self, name: &str) -> Arc<str> {
+        if let Some(interned) = self.names.get(name) {
+            return interned.clone();
+        }
+
+        let interned: Arc<str> = Arc::from(name);
+        self.names.insert(interned.clone());
+        interned
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl DynamicScene {
+    /// Returns each component type name used by this scene once, in the order it first appears.
+    /// Names are formatted the way the scene serializer writes them and are taken from `interner`.
+    pub fn component_type_names(
+        &self,
+        registry: &PropertyTypeRegistry,
+        interner: &mut TypeNameInterner,
+    ) -> Vec<Arc<str>> {
+        let mut type_names: Vec<Arc<str>> = Vec::new();
+        for entity in self.entities.iter() {
+            for component in entity.components.iter() {
+                let type_name = registry
+                    .format_type_name(&component.type_name)
+                    .unwrap_or(&component.type_name);
+                if !type_names.iter().any(|name| &**name == type_name) {
+                    type_names.push(interner.intern(type_name));
+                }
+            }
+        }
+
+        type_names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypeNameInterner;
+    use crate::{
+        test_support::{sample_type_registry, SamplePosition},
+        DynamicScene,
+    };
+    use bevy_ecs::World;
+    use bevy_property::Properties;
+    use std::sync::Arc;
+
+    #[derive(Properties, Default)]
+    struct Health {
+        value: u32,
+    }
+
+    #[test]
+    fn scenes_share_interned_names() {
+        let type_registry = sample_type_registry();
+        type_registry.component.write().register::<Health>();
+        let component_registry = type_registry.component.read();
+        let property_type_registry = type_registry.property.read();
+
+        let mut world = World::default();
+        world.spawn((
+            SamplePosition {
+                x: 1.0,
+                y: 2.0,
+                z: 0.0,
+            },
+            Health { value: 10 },
+        ));
+        world.spawn((SamplePosition {
+            x: 3.0,
+            y: 4.0,
+            z: 0.0,
+        },));
+        let first = DynamicScene::from_world(&world, &component_registry);
+
+        let mut world = World::default();
+        world.spawn((SamplePosition {
+            x: 5.0,
+            y: 6.0,
+            z: 0.0,
+        },));
+        let second = DynamicScene::from_world(&world, &component_registry);
+
+        let mut interner = TypeNameInterner::default();
+        let first_names = first.component_type_names(&property_type_registry, &mut interner);
+        let second_names = second.component_type_names(&property_type_registry, &mut interner);
+        assert_eq!(first_names.len(), 2);
+        assert_eq!(second_names.len(), 1);
+        assert_eq!(interner.len(), 2);
+
+        let position = first_names
+            .iter()
+            .find(|name| name.ends_with("SamplePosition"))
+            .unwrap();
+        assert!(Arc::ptr_eq(position, &second_names[0]));
+    }
+}
@@ -1,11 +1,14 @@
 use bevy_ecs::{Entity, MapEntities};
 use bevy_property::Properties;
+use bevy_type_registry::RetainEntities;
 use smallvec::SmallVec;
 use std::ops::{Deref, DerefMut};
 
 #[derive(Default, Clone, Properties, Debug)]
 pub struct Children(pub SmallVec<[Entity; 8]>);
 
+/// Children keep their order. A child that isn't in the map is an error; children filtered out
+/// of a scene are removed when it is saved, see [RetainEntities].
 impl MapEntities for Children {
     fn map_entities(
         &mut self,
@@ -19,6 +22,13 @@ impl MapEntities for Children {
     }
 }
 
+/// Removes the children that aren't saved without leaving a gap, keeping the order of the rest.
+impl RetainEntities for Children {
+    fn retain_entities(&mut self, keep: &dyn Fn(Entity) -> bool) {
+        self.0.retain(|entity| keep(*entity));
+    }
+}
+
 impl Children {
     pub fn with(entity: &[Entity]) -> Self {
         Self(SmallVec::from_slice(entity))
@@ -38,3 +48,102 @@ impl DerefMut for Children {
         &mut self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Children;
+    use bevy_ecs::{Entity, EntityMap, Resources, World};
+    use bevy_scene::DynamicScene;
+    use bevy_type_registry::{ComponentRegistration, TypeRegistry};
+
+    struct Transient;
+
+    fn children_registration() -> ComponentRegistration {
+        ComponentRegistration::build::<Children>()
+            .map_entities()
+            .retain_entities()
+            .finish()
+    }
+
+    #[test]
+    fn map_entities_keeps_order() {
+        let registration = children_registration();
+        let mut world = World::default();
+        let children = (0..4).map(|_| world.spawn(())).collect::<Vec<_>>();
+        let parent = world.spawn((Children::with(&children),));
+        let archetype = world
+            .archetypes()
+            .find(|archetype| archetype.has::<Children>())
+            .unwrap();
+        // the third child is filtered out when saving
+        let saved = registration
+            .get_serialized_component_retaining(archetype, 0, &|entity| entity != children[2])
+            .unwrap();
+
+        let mut loaded_world = World::default();
+        let mut entity_map = EntityMap::default();
+        let loaded_parent = loaded_world.spawn(());
+        entity_map.insert(parent, loaded_parent);
+        for &child in [children[0], children[1], children[3]].iter() {
+            entity_map.insert(child, loaded_world.spawn(()));
+        }
+        registration.add_property_to_entity(
+            &mut loaded_world,
+            &Resources::default(),
+            loaded_parent,
+            &saved,
+        );
+        registration
+            .map_entities(&mut loaded_world, &entity_map)
+            .unwrap();
+
+        let expected = [children[0], children[1], children[3]]
+            .iter()
+            .map(|child| entity_map.get(*child).unwrap())
+            .collect::<Vec<Entity>>();
+        assert_eq!(
+            loaded_world
+                .get::<Children>(loaded_parent)
+                .unwrap()
+                .to_vec(),
+            expected
+        );
+    }
+
+    #[test]
+    fn filtered_children_round_trip() {
+        let type_registry = TypeRegistry::default();
+        type_registry
+            .component
+            .write()
+            .add_registration(children_registration());
+        let mut world = World::default();
+        let children = (0..4)
+            .map(|index| {
+                if index == 2 {
+                    world.spawn((Transient,))
+                } else {
+                    world.spawn(())
+                }
+            })
+            .collect::<Vec<_>>();
+        world.spawn((Children::with(&children),));
+        let mut resources = Resources::default();
+        resources.insert(type_registry.clone());
+
+        let scene = DynamicScene::from_world_excluding::<Transient>(
+            &world,
+            &type_registry.component.read(),
+        );
+        let mut loaded_world = World::default();
+        scene.write_to_world(&mut loaded_world, &resources).unwrap();
+        let loaded_children = loaded_world.query::<&Children>().next().unwrap().len();
+        assert_eq!(loaded_children, 3);
+
+        // a child that is missing without having been filtered out is still an error
+        world.despawn(children[2]).unwrap();
+        let scene = DynamicScene::from_world(&world, &type_registry.component.read());
+        let mut loaded_world = World::default();
+        assert!(scene.write_to_world(&mut loaded_world, &resources).is_err());
+    }
+}
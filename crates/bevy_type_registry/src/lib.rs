@@ -1,8 +1,10 @@
 mod register_type;
+mod relative_instant;
 mod type_registry;
 mod type_uuid;
 
 pub use register_type::*;
+pub use relative_instant::*;
 pub use type_registry::*;
 pub use type_uuid::*;
 pub use uuid::Uuid;
@@ -15,7 +17,9 @@ pub struct TypeRegistryPlugin;
 
 impl Plugin for TypeRegistryPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.init_resource::<TypeRegistry>()
-            .register_property::<DynamicProperties>();
+        // registrations made before the plugin was added are kept
+        app.resources_mut()
+            .get_or_insert_with(TypeRegistry::default);
+        app.register_property::<DynamicProperties>();
     }
 }
@@ -1,6 +1,6 @@
 use crate::{ComponentRegistration, ComponentRegistrationBuilder, TypeRegistry};
 use bevy_app::AppBuilder;
-use bevy_ecs::{Component, FromResources};
+use bevy_ecs::{Component, FromResources, Resource};
 use bevy_property::{DeserializeProperty, Properties, Property};
 
 pub trait RegisterType {
@@ -13,6 +13,9 @@ pub trait RegisterType {
     ) -> &mut Self
     where
         T: Properties + DeserializeProperty + Component + FromResources;
+    fn register_resource<T>(&mut self) -> &mut Self
+    where
+        T: Properties + DeserializeProperty + Resource + FromResources;
     fn register_properties<T>(&mut self) -> &mut Self
     where
         T: Properties + DeserializeProperty + FromResources;
@@ -21,25 +24,44 @@ pub trait RegisterType {
         T: Property + DeserializeProperty;
 }
 
+/// Returns the app's [TypeRegistry], inserting an empty one first if it doesn't have one yet.
+fn type_registry(app: &mut AppBuilder) -> TypeRegistry {
+    app.resources_mut()
+        .get_or_insert_with(TypeRegistry::default)
+        .clone()
+}
+
 impl RegisterType for AppBuilder {
     fn register_component<T>(&mut self) -> &mut Self
     where
         T: Properties + DeserializeProperty + Component + FromResources,
     {
         {
-            let type_registry = self.app.resources.get::<TypeRegistry>().unwrap();
+            let type_registry = type_registry(self);
             type_registry.component.write().register::<T>();
             type_registry.property.write().register::<T>();
         }
         self
     }
 
+    fn register_resource<T>(&mut self) -> &mut Self
+    where
+        T: Properties + DeserializeProperty + Resource + FromResources,
+    {
+        {
+            let type_registry = type_registry(self);
+            type_registry.resource.write().register::<T>();
+            type_registry.property.write().register::<T>();
+        }
+        self
+    }
+
     fn register_properties<T>(&mut self) -> &mut Self
     where
         T: Properties + DeserializeProperty + Component + FromResources,
     {
         {
-            let type_registry = self.app.resources.get::<TypeRegistry>().unwrap();
+            let type_registry = type_registry(self);
             type_registry.property.write().register::<T>();
         }
         self
@@ -50,7 +72,7 @@ impl RegisterType for AppBuilder {
         T: Property + DeserializeProperty,
     {
         {
-            let type_registry = self.app.resources.get::<TypeRegistry>().unwrap();
+            let type_registry = type_registry(self);
             type_registry.property.write().register::<T>();
         }
         self
@@ -66,7 +88,7 @@ impl RegisterType for AppBuilder {
         {
             let mut builder = ComponentRegistration::build::<T>();
             builder = build(builder);
-            let type_registry = self.app.resources.get::<TypeRegistry>().unwrap();
+            let type_registry = type_registry(self);
             type_registry
                 .component
                 .write()
@@ -76,3 +98,41 @@ impl RegisterType for AppBuilder {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RegisterType;
+    use crate::{TypeRegistry, TypeRegistryPlugin};
+    use bevy_app::App;
+    use bevy_property::Properties;
+
+    #[derive(Properties, Default)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Properties, Default)]
+    struct Velocity {
+        x: f32,
+        y: f32,
+    }
+
+    #[test]
+    fn register_components() {
+        let mut app = App::build();
+        app.register_component::<Position>()
+            .add_plugin(TypeRegistryPlugin)
+            .register_component::<Velocity>();
+
+        let type_registry = app.resources().get::<TypeRegistry>().unwrap();
+        let component_registry = type_registry.component.read();
+        assert!(component_registry.is_registered::<Position>());
+        assert!(component_registry.is_registered::<Velocity>());
+        assert!(type_registry
+            .property
+            .read()
+            .get(std::any::type_name::<Velocity>())
+            .is_some());
+    }
+}
@@ -0,0 +1,97 @@
+use bevy_property::Properties;
+use std::time::{Duration, Instant};
+
+/// An [Instant] field that can be saved. [Instant]s only make sense within the process that
+/// created them, so the instant is stored as an offset from the moment of saving instead:
+/// convert it with [RelativeInstant::save_relative_to] in [SerializeHooks::pre_serialize] and
+/// back with [RelativeInstant::restore_relative_to] in [SerializeHooks::post_deserialize].
+///
+/// [SerializeHooks::pre_serialize]: crate::SerializeHooks::pre_serialize
+/// [SerializeHooks::post_deserialize]: crate::SerializeHooks::post_deserialize
+#[derive(Properties, Debug, Clone, Default)]
+pub struct RelativeInstant {
+    /// The distance between the instant and the moment it was saved.
+    pub offset: Duration,
+    /// Whether the instant lies before the moment it was saved.
+    pub in_past: bool,
+    #[property(ignore)]
+    instant: Option<Instant>,
+}
+
+impl RelativeInstant {
+    pub fn new(instant: Instant) -> Self {
+        RelativeInstant {
+            instant: Some(instant),
+            ..Default::default()
+        }
+    }
+
+    /// The instant, or `None` if it hasn't been restored with
+    /// [RelativeInstant::restore_relative_to] since loading.
+    pub fn instant(&self) -> Option<Instant> {
+        self.instant
+    }
+
+    pub fn set_instant(&mut self, instant: Instant) {
+        self.instant = Some(instant);
+    }
+
+    /// Stores the instant as an offset from `now`.
+    pub fn save_relative_to(&mut self, now: Instant) {
+        if let Some(instant) = self.instant {
+            self.in_past = instant < now;
+            self.offset = if self.in_past {
+                now - instant
+            } else {
+                instant - now
+            };
+        }
+    }
+
+    /// Recreates the instant from the stored offset, taking `now` as the moment of saving.
+    /// Offsets are kept in whole nanoseconds, so the instant is restored exactly; offsets reaching
+    /// before the platform's earliest representable instant are clamped to `now`.
+    pub fn restore_relative_to(&mut self, now: Instant) {
+        self.instant = Some(if self.in_past {
+            now.checked_sub(self.offset).unwrap_or(now)
+        } else {
+            now + self.offset
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RelativeInstant;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn relative_instant() {
+        let saved_at = Instant::now();
+        let offset = Duration::new(5, 123_456_789);
+        let mut ready_at = RelativeInstant::new(saved_at + offset);
+        ready_at.save_relative_to(saved_at);
+        assert_eq!(ready_at.offset, offset);
+        assert!(!ready_at.in_past);
+
+        let mut loaded = RelativeInstant {
+            offset: ready_at.offset,
+            in_past: ready_at.in_past,
+            ..Default::default()
+        };
+        assert_eq!(loaded.instant(), None);
+        let loaded_at = saved_at + Duration::from_secs(60);
+        loaded.restore_relative_to(loaded_at);
+        assert_eq!(loaded.instant(), Some(loaded_at + offset));
+
+        let mut started_at = RelativeInstant::new(saved_at);
+        started_at.save_relative_to(saved_at + Duration::from_nanos(1));
+        assert_eq!(started_at.offset, Duration::from_nanos(1));
+        assert!(started_at.in_past);
+        started_at.restore_relative_to(loaded_at);
+        assert_eq!(
+            started_at.instant(),
+            Some(loaded_at - Duration::from_nanos(1))
+        );
+    }
+}
@@ -1,29 +1,93 @@
 use bevy_ecs::{
-    Archetype, Component, Entity, EntityMap, FromResources, MapEntities, MapEntitiesError,
-    Resources, World,
+    Archetype, Component, Entity, EntityBuilder, EntityMap, FromResources, MapEntities,
+    MapEntitiesError, Resource, Resources, World,
 };
 use bevy_property::{
-    DeserializeProperty, Properties, Property, PropertyTypeRegistration, PropertyTypeRegistry,
+    property_serde::DynamicPropertiesSerializer, DeserializeProperty, DynamicProperties,
+    Properties, Property, PropertyTypeRegistration, PropertyTypeRegistry,
 };
-use bevy_utils::{HashMap, HashSet};
+use bevy_utils::{stable_hash, HashMap, HashSet};
 use parking_lot::RwLock;
-use std::{any::TypeId, marker::PhantomData, sync::Arc};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{any::TypeId, collections::BTreeMap, marker::PhantomData, sync::Arc};
+
+/// The name of the single field that holds a blob component's encoded bytes.
+pub const BLOB_FIELD: &str = "blob";
 
 #[derive(Clone, Default)]
 pub struct TypeRegistry {
     pub property: Arc<RwLock<PropertyTypeRegistry>>,
     pub component: Arc<RwLock<ComponentRegistry>>,
+    pub resource: Arc<RwLock<ResourceRegistry>>,
+}
+
+/// Looks up registered types by their short or full name. A short name used by more than one
+/// type is ambiguous, so those types can only be found by their full name.
+#[derive(Default)]
+pub struct TypeNameIndex {
+    short_names: HashMap<String, TypeId>,
+    full_names: HashMap<String, TypeId>,
+    ambiguous_names: HashSet<String>,
+}
+
+impl TypeNameIndex {
+    pub fn insert(&mut self, short_name: &str, full_name: &str, ty: TypeId) {
+        self.full_names.insert(full_name.to_string(), ty);
+        if self.short_names.contains_key(short_name) || self.ambiguous_names.contains(short_name) {
+            // name is ambiguous. fall back to long names for all ambiguous types
+            self.short_names.remove(short_name);
+            self.ambiguous_names.insert(short_name.to_string());
+        } else {
+            self.short_names.insert(short_name.to_string(), ty);
+        }
+    }
+
+    /// Removes a type's names. `remaining` are the types still registered under the same short
+    /// name.
+    pub fn remove(&mut self, short_name: &str, full_name: &str, remaining: &[TypeId]) {
+        self.full_names.remove(full_name);
+        self.short_names.remove(short_name);
+        if self.ambiguous_names.contains(short_name) {
+            // the short name is unambiguous again once only one type uses it
+            if remaining.len() <= 1 {
+                self.ambiguous_names.remove(short_name);
+            }
+            if let [ty] = remaining[..] {
+                self.short_names.insert(short_name.to_string(), ty);
+            }
+        }
+    }
+
+    pub fn get_with_short_name(&self, short_name: &str) -> Option<TypeId> {
+        self.short_names.get(short_name).cloned()
+    }
+
+    pub fn get_with_full_name(&self, full_name: &str) -> Option<TypeId> {
+        self.full_names.get(full_name).cloned()
+    }
+
+    /// Returns the type with the given short or full name.
+    pub fn get_with_name(&self, type_name: &str) -> Option<TypeId> {
+        self.get_with_short_name(type_name)
+            .or_else(|| self.get_with_full_name(type_name))
+    }
+
+    pub fn is_ambiguous(&self, short_name: &str) -> bool {
+        self.ambiguous_names.contains(short_name)
+    }
 }
 
 #[derive(Default)]
 pub struct ComponentRegistry {
     pub registrations: HashMap<TypeId, ComponentRegistration>,
-    pub short_names: HashMap<String, TypeId>,
-    pub full_names: HashMap<String, TypeId>,
-    pub ambigous_names: HashSet<String>,
+    pub names: TypeNameIndex,
 }
 
 impl ComponentRegistry {
+    /// Registers `T`. Loaded components are created with [FromResources] before their serialized
+    /// properties are applied, so a custom [FromResources] impl acts as a factory that can pull
+    /// values which aren't saved, such as asset handles, from the app's resources. Saved fields
+    /// that `T` doesn't have, e.g. ones written by a newer version of the type, are ignored.
     pub fn register<T>(&mut self)
     where
         T: Properties + Component + FromResources,
@@ -31,41 +95,89 @@ impl ComponentRegistry {
         self.add_registration(ComponentRegistration::of::<T>());
     }
 
+    /// Registers `T` along with its [Default] value, which is used as the example in
+    /// [ComponentRegistry::export_schema].
+    pub fn register_default<T>(&mut self)
+    where
+        T: Properties + DeserializeProperty + Component + FromResources + Default,
+    {
+        self.add_registration(ComponentRegistration::build::<T>().with_default().finish());
+    }
+
+    /// Registers `T` with a doc string that is written above its entries in documented scene
+    /// files.
+    pub fn register_with_doc<T>(&mut self, doc: &'static str)
+    where
+        T: Properties + DeserializeProperty + Component + FromResources,
+    {
+        self.add_registration(ComponentRegistration::build::<T>().with_doc(doc).finish());
+    }
+
     pub fn add_registration(&mut self, registration: ComponentRegistration) {
-        let short_name = registration.short_name.to_string();
-        self.full_names
-            .insert(registration.long_name.to_string(), registration.ty);
-        if self.short_names.contains_key(&short_name) || self.ambigous_names.contains(&short_name) {
-            // name is ambiguous. fall back to long names for all ambiguous types
-            self.short_names.remove(&short_name);
-            self.ambigous_names.insert(short_name);
-        } else {
-            self.short_names.insert(short_name, registration.ty);
-        }
+        self.names.insert(
+            &registration.short_name,
+            registration.long_name,
+            registration.ty,
+        );
         self.registrations.insert(registration.ty, registration);
     }
 
+    /// Removes the registration of `T`, returning whether it was registered. Call this when the
+    /// code that registered `T` is unloaded so no function pointers into it are left behind.
+    pub fn unregister<T: Component>(&mut self) -> bool {
+        self.unregister_type_id(&TypeId::of::<T>())
+    }
+
+    /// Removes the registration with the given short or full type name, returning whether one was
+    /// found.
+    pub fn unregister_by_name(&mut self, type_name: &str) -> bool {
+        match self.names.get_with_name(type_name) {
+            Some(type_id) => self.unregister_type_id(&type_id),
+            None => false,
+        }
+    }
+
+    fn unregister_type_id(&mut self, type_id: &TypeId) -> bool {
+        let registration = match self.registrations.remove(type_id) {
+            Some(registration) => registration,
+            None => return false,
+        };
+        let remaining = self
+            .registrations
+            .values()
+            .filter(|other| other.short_name == registration.short_name)
+            .map(|other| other.ty)
+            .collect::<Vec<_>>();
+        self.names
+            .remove(&registration.short_name, registration.long_name, &remaining);
+        true
+    }
+
+    pub fn is_registered<T: Component>(&self) -> bool {
+        self.registrations.contains_key(&TypeId::of::<T>())
+    }
+
     pub fn get(&self, type_id: &TypeId) -> Option<&ComponentRegistration> {
         self.registrations.get(type_id)
     }
 
     pub fn get_with_full_name(&self, full_name: &str) -> Option<&ComponentRegistration> {
-        self.full_names
-            .get(full_name)
-            .and_then(|id| self.registrations.get(id))
+        self.names
+            .get_with_full_name(full_name)
+            .and_then(|id| self.registrations.get(&id))
     }
 
     pub fn get_with_short_name(&self, short_name: &str) -> Option<&ComponentRegistration> {
-        self.short_names
-            .get(short_name)
-            .and_then(|id| self.registrations.get(id))
+        self.names
+            .get_with_short_name(short_name)
+            .and_then(|id| self.registrations.get(&id))
     }
 
     pub fn get_with_name(&self, type_name: &str) -> Option<&ComponentRegistration> {
         let mut registration = self.get_with_short_name(type_name);
         if registration.is_none() {
             registration = self.get_with_full_name(type_name);
-            if registration.is_none() && self.ambigous_names.contains(type_name) {
+            if registration.is_none() && self.names.is_ambiguous(type_name) {
                 panic!("Type name is ambiguous: {}", type_name);
             }
         }
@@ -75,20 +187,87 @@ impl ComponentRegistry {
     pub fn iter(&self) -> impl Iterator<Item = &ComponentRegistration> {
         self.registrations.values()
     }
+
+    /// Serializes the default value of every component registered with a default to JSON, keyed
+    /// by the component's full type name. Components without a default are left out.
+    pub fn export_schema(
+        &self,
+        property_type_registry: &PropertyTypeRegistry,
+    ) -> Result<BTreeMap<&'static str, serde_json::Value>, serde_json::Error> {
+        let mut schema = BTreeMap::new();
+        for registration in self.iter() {
+            if let Some(default_properties) = registration.get_default_properties() {
+                let value = serde_json::to_value(DynamicPropertiesSerializer::new(
+                    &default_properties,
+                    property_type_registry,
+                ))?;
+                schema.insert(registration.long_name, value);
+            }
+        }
+
+        Ok(schema)
+    }
+
+    /// Describes every registration for debugging, e.g. to find out why a component doesn't
+    /// load: its names, [TypeId], [ComponentRegistration::name_hash] and which optional
+    /// functions are set. Registrations are sorted by full name and separated by blank lines.
+    pub fn describe(&self) -> String {
+        let mut registrations = self.iter().collect::<Vec<_>>();
+        registrations.sort_by_key(|registration| registration.long_name);
+        let yes_no = |set: bool| if set { "yes" } else { "no" };
+        registrations
+            .iter()
+            .map(|registration| {
+                format!(
+                    "{}\n  full name: {}\n  type id: {:?}\n  name hash: {:016x}\n  default: {}\n  \
+                     resolve from resources: {}\n  doc: {}\n",
+                    registration.short_name,
+                    registration.long_name,
+                    registration.ty,
+                    registration.name_hash(),
+                    yes_no(registration.default_properties_fn.is_some()),
+                    yes_no(registration.component_resolve_fn.is_some()),
+                    yes_no(registration.doc.is_some()),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
+/// Type-erased functions used to add, apply, copy and inspect a registered component.
+///
+/// [ComponentRegistration::of] and [ComponentRegistration::build] fill these in for
+/// [Properties] types, but every field is public so integrations with unusual component types can
+/// supply their own functions.
 #[derive(Clone)]
 pub struct ComponentRegistration {
     pub ty: TypeId,
     pub short_name: String,
     pub long_name: &'static str,
-    component_add_fn: fn(&mut World, resources: &Resources, Entity, &dyn Property),
-    component_apply_fn: fn(&mut World, Entity, &dyn Property),
-    component_properties_fn: fn(&Archetype, usize) -> &dyn Properties,
-    component_copy_fn: fn(&World, &mut World, &Resources, Entity, Entity),
-    copy_to_scene_fn: fn(&World, &mut World, &Resources, Entity, Entity),
-    copy_from_scene_fn: fn(&World, &mut World, &Resources, Entity, Entity),
-    map_entities_fn: fn(&mut World, &EntityMap) -> Result<(), MapEntitiesError>,
+    pub component_add_fn: fn(&mut World, resources: &Resources, Entity, &dyn Property),
+    pub component_apply_fn: fn(&mut World, Entity, &dyn Property),
+    pub component_build_fn: fn(&mut EntityBuilder, &Resources, &dyn Property),
+    pub component_properties_fn: fn(&Archetype, usize) -> &dyn Properties,
+    /// Fails if the component can't be represented as properties, e.g. a blob that doesn't
+    /// encode.
+    pub component_serialize_fn: fn(&Archetype, usize) -> Result<DynamicProperties, String>,
+    pub component_copy_fn: fn(&World, &mut World, &Resources, Entity, Entity),
+    pub copy_to_scene_fn: fn(&World, &mut World, &Resources, Entity, Entity),
+    pub copy_from_scene_fn: fn(&World, &mut World, &Resources, Entity, Entity),
+    pub map_entities_fn: fn(&mut World, &EntityMap) -> Result<(), MapEntitiesError>,
+    pub default_properties_fn: Option<fn() -> DynamicProperties>,
+    pub component_resolve_fn: Option<fn(&mut World, &Resources, Entity)>,
+    /// A short description of the component, written as a comment by documented RON output.
+    pub doc: Option<&'static str>,
+    /// Fields that hold per-instance data, such as ids or spawn positions, and are left out of
+    /// serialized templates.
+    pub instance_fields: &'static [&'static str],
+    /// Checks that loaded properties can be turned into the component before they are added or
+    /// applied, for components whose add functions can't report errors.
+    pub component_validate_fn: Option<fn(&dyn Property) -> Result<(), String>>,
+    pub component_serialize_retaining_fn:
+        Option<fn(&Archetype, usize, &dyn Fn(Entity) -> bool) -> DynamicProperties>,
 }
 
 struct ComponentRegistrationDefaults;
@@ -105,6 +284,16 @@ impl ComponentRegistrationDefaults {
         world.insert_one(entity, component).unwrap();
     }
 
+    fn component_build<T: Component + Properties + FromResources>(
+        builder: &mut EntityBuilder,
+        resources: &Resources,
+        property: &dyn Property,
+    ) {
+        let mut component = T::from_resources(resources);
+        component.apply(property);
+        builder.add(component);
+    }
+
     fn component_apply<T: Component + Properties>(
         world: &mut World,
         entity: Entity,
@@ -140,6 +329,13 @@ impl ComponentRegistrationDefaults {
         }
     }
 
+    fn component_serialize<T: Component + Properties>(
+        archetype: &Archetype,
+        index: usize,
+    ) -> Result<DynamicProperties, String> {
+        Ok(Self::component_properties::<T>(archetype, index).to_dynamic())
+    }
+
     fn map_entities(_world: &mut World, _entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
         Ok(())
     }
@@ -162,16 +358,34 @@ impl ComponentRegistration {
             ty,
             component_add_fn: ComponentRegistrationDefaults::component_add::<T>,
             component_apply_fn: ComponentRegistrationDefaults::component_apply::<T>,
+            component_build_fn: ComponentRegistrationDefaults::component_build::<T>,
             component_copy_fn: ComponentRegistrationDefaults::component_copy::<T>,
             component_properties_fn: ComponentRegistrationDefaults::component_properties::<T>,
+            component_serialize_fn: ComponentRegistrationDefaults::component_serialize::<T>,
             copy_from_scene_fn: ComponentRegistrationDefaults::component_copy::<T>,
             copy_to_scene_fn: ComponentRegistrationDefaults::component_copy::<T>,
             map_entities_fn: ComponentRegistrationDefaults::map_entities,
+            default_properties_fn: None,
+            component_resolve_fn: None,
+            doc: None,
+            instance_fields: &[],
+            component_validate_fn: None,
+            component_serialize_retaining_fn: None,
             short_name: PropertyTypeRegistration::get_short_name(std::any::type_name::<T>()),
             long_name: std::any::type_name::<T>(),
         }
     }
 
+    /// Returns why `property` can't be added to or applied to an entity as this component, e.g.
+    /// because a blob read from a damaged scene file doesn't decode. Scene writers call this
+    /// first and report the error instead of writing the component.
+    pub fn validate_property(&self, property: &dyn Property) -> Result<(), String> {
+        match self.component_validate_fn {
+            Some(component_validate_fn) => component_validate_fn(property),
+            None => Ok(()),
+        }
+    }
+
     pub fn add_property_to_entity(
         &self,
         world: &mut World,
@@ -191,6 +405,17 @@ impl ComponentRegistration {
         (self.component_apply_fn)(world, entity, property);
     }
 
+    /// Adds the component to `builder`, so an entity can be spawned with all of its components
+    /// at once instead of moving through an archetype per inserted component.
+    pub fn add_property_to_builder(
+        &self,
+        builder: &mut EntityBuilder,
+        resources: &Resources,
+        property: &dyn Property,
+    ) {
+        (self.component_build_fn)(builder, resources, property);
+    }
+
     pub fn get_component_properties<'a>(
         &self,
         archetype: &'a Archetype,
@@ -199,6 +424,35 @@ impl ComponentRegistration {
         (self.component_properties_fn)(archetype, entity_index)
     }
 
+    /// Returns the properties of the component as they should be saved, after any
+    /// [SerializeHooks::pre_serialize] hook has run.
+    pub fn get_serialized_component(
+        &self,
+        archetype: &Archetype,
+        entity_index: usize,
+    ) -> Result<DynamicProperties, String> {
+        (self.component_serialize_fn)(archetype, entity_index)
+    }
+
+    /// Like [ComponentRegistration::get_serialized_component], but drops the component's
+    /// references to entities `keep` returns false for if it was registered with
+    /// [ComponentRegistrationBuilder::retain_entities].
+    pub fn get_serialized_component_retaining(
+        &self,
+        archetype: &Archetype,
+        entity_index: usize,
+        keep: &dyn Fn(Entity) -> bool,
+    ) -> Result<DynamicProperties, String> {
+        match self.component_serialize_retaining_fn {
+            Some(component_serialize_retaining_fn) => Ok(component_serialize_retaining_fn(
+                archetype,
+                entity_index,
+                keep,
+            )),
+            None => self.get_serialized_component(archetype, entity_index),
+        }
+    }
+
     pub fn component_copy(
         &self,
         source_world: &World,
@@ -257,6 +511,25 @@ impl ComponentRegistration {
     ) -> Result<(), MapEntitiesError> {
         (self.map_entities_fn)(world, entity_map)
     }
+
+    /// Runs the component's [ResolveFromResources] impl, if it was registered with one. Call this
+    /// after adding or applying loaded properties to `entity`.
+    pub fn resolve_component(&self, world: &mut World, resources: &Resources, entity: Entity) {
+        if let Some(component_resolve_fn) = self.component_resolve_fn {
+            component_resolve_fn(world, resources, entity);
+        }
+    }
+
+    pub fn get_default_properties(&self) -> Option<DynamicProperties> {
+        self.default_properties_fn
+            .map(|default_properties_fn| default_properties_fn())
+    }
+
+    /// An FNV-1a hash of the full type name. Unlike [TypeId], it is the same across builds and
+    /// compiler versions as long as the type's path doesn't change.
+    pub fn name_hash(&self) -> u64 {
+        stable_hash(self.long_name.as_bytes())
+    }
 }
 
 pub struct ComponentRegistrationBuilder<T> {
@@ -323,11 +596,583 @@ where
         self
     }
 
+    /// Uses the component's [RetainEntities] impl to drop references to entities left out of a
+    /// scene, e.g. by [ComponentRegistration::get_serialized_component_retaining]. The retained
+    /// copy is serialized with [Properties::to_dynamic], so combine this with neither
+    /// [ComponentRegistrationBuilder::serialize_hooks] nor [ComponentRegistrationBuilder::blob].
+    pub fn retain_entities(mut self) -> Self
+    where
+        T: RetainEntities + Clone,
+    {
+        self.registration.component_serialize_retaining_fn = Some(
+            |archetype: &Archetype, index: usize, keep: &dyn Fn(Entity) -> bool| {
+                let mut component =
+                    ComponentRegistrationDefaults::component_properties::<T>(archetype, index)
+                        .any()
+                        .downcast_ref::<T>()
+                        .unwrap()
+                        .clone();
+                component.retain_entities(keep);
+                component.to_dynamic()
+            },
+        );
+        self
+    }
+
+    /// Runs the component's [SerializeHooks] whenever it is serialized or added from serialized
+    /// properties.
+    pub fn serialize_hooks(mut self) -> Self
+    where
+        T: SerializeHooks + Clone,
+    {
+        self.registration.component_serialize_fn = |archetype: &Archetype, index: usize| {
+            let mut component =
+                ComponentRegistrationDefaults::component_properties::<T>(archetype, index)
+                    .any()
+                    .downcast_ref::<T>()
+                    .unwrap()
+                    .clone();
+            component.pre_serialize();
+            Ok(component.to_dynamic())
+        };
+        self.registration.component_add_fn =
+            |world: &mut World, resources: &Resources, entity: Entity, property: &dyn Property| {
+                let mut component = T::from_resources(resources);
+                component.apply(property);
+                component.post_deserialize();
+                world.insert_one(entity, component).unwrap();
+            };
+        self.registration.component_build_fn =
+            |builder: &mut EntityBuilder, resources: &Resources, property: &dyn Property| {
+                let mut component = T::from_resources(resources);
+                component.apply(property);
+                component.post_deserialize();
+                builder.add(component);
+            };
+        self.registration.component_apply_fn =
+            |world: &mut World, entity: Entity, property: &dyn Property| {
+                let mut component = world.get_mut::<T>(entity).unwrap();
+                component.apply(property);
+                component.post_deserialize();
+            };
+        self
+    }
+
+    /// Stores the component as an opaque blob: it is encoded with bincode and saved as a single
+    /// base64 string in the [BLOB_FIELD] field instead of one entry per property. Blobs that
+    /// don't decode fail [ComponentRegistration::validate_property] and are otherwise skipped.
+    /// Components that don't encode fail [ComponentRegistration::get_serialized_component].
+    pub fn blob(mut self) -> Self
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        self.registration.component_serialize_fn = |archetype: &Archetype, index: usize| {
+            let component =
+                ComponentRegistrationDefaults::component_properties::<T>(archetype, index)
+                    .any()
+                    .downcast_ref::<T>()
+                    .unwrap();
+            let bytes = bincode::serialize(component)
+                .map_err(|error| format!("failed to encode blob: {}", error))?;
+            let mut properties = DynamicProperties::map();
+            properties.type_name = std::any::type_name::<T>().to_string();
+            properties.set(BLOB_FIELD, base64::encode(&bytes));
+            Ok(properties)
+        };
+        self.registration.component_validate_fn =
+            Some(|property: &dyn Property| decode_blob::<T>(property).map(|_| ()));
+        self.registration.component_add_fn =
+            |world: &mut World, _resources: &Resources, entity: Entity, property: &dyn Property| {
+                if let Ok(component) = decode_blob::<T>(property) {
+                    world.insert_one(entity, component).unwrap();
+                }
+            };
+        self.registration.component_apply_fn =
+            |world: &mut World, entity: Entity, property: &dyn Property| {
+                if let Ok(component) = decode_blob::<T>(property) {
+                    *world.get_mut::<T>(entity).unwrap() = component;
+                }
+            };
+        self.registration.component_build_fn =
+            |builder: &mut EntityBuilder, _resources: &Resources, property: &dyn Property| {
+                if let Ok(component) = decode_blob::<T>(property) {
+                    builder.add(component);
+                }
+            };
+        self
+    }
+
+    pub fn resolve_from_resources(mut self) -> Self
+    where
+        T: ResolveFromResources,
+    {
+        self.registration.component_resolve_fn =
+            Some(|world: &mut World, resources: &Resources, entity: Entity| {
+                let mut component = world.get_mut::<T>(entity).unwrap();
+                component.resolve_from_resources(resources);
+            });
+        self
+    }
+
+    pub fn with_default(mut self) -> Self
+    where
+        T: Default,
+    {
+        self.registration.default_properties_fn = Some(|| T::default().to_dynamic());
+        self
+    }
+
+    pub fn with_doc(mut self, doc: &'static str) -> Self {
+        self.registration.doc = Some(doc);
+        self
+    }
+
+    pub fn with_instance_fields(mut self, fields: &'static [&'static str]) -> Self {
+        self.registration.instance_fields = fields;
+        self
+    }
+
     pub fn finish(self) -> ComponentRegistration {
         self.registration
     }
 }
 
+fn decode_blob<T: DeserializeOwned>(property: &dyn Property) -> Result<T, String> {
+    let encoded = property
+        .as_properties()
+        .and_then(|properties| properties.prop(BLOB_FIELD))
+        .and_then(|blob| blob.any().downcast_ref::<String>())
+        .ok_or_else(|| format!("missing '{}' string", BLOB_FIELD))?;
+    let bytes =
+        base64::decode(encoded).map_err(|error| format!("blob is not valid base64: {}", error))?;
+    bincode::deserialize(&bytes).map_err(|error| format!("blob doesn't decode: {}", error))
+}
+
 pub trait IntoComponent<ToComponent: Component> {
     fn into_component(&self, resources: &Resources) -> ToComponent;
 }
+
+/// Fills in parts of a loaded component that aren't saved, for example an asset handle looked up
+/// from a stored asset path. Enable it with [ComponentRegistrationBuilder::resolve_from_resources].
+pub trait ResolveFromResources {
+    fn resolve_from_resources(&mut self, resources: &Resources);
+}
+
+/// Drops references to entities that aren't saved along with the component, such as children
+/// filtered out of a scene. Enable it with [ComponentRegistrationBuilder::retain_entities].
+pub trait RetainEntities {
+    fn retain_entities(&mut self, keep: &dyn Fn(Entity) -> bool);
+}
+
+/// Hooks that convert a component between its runtime form and the canonical form it is saved in.
+/// Enable them with [ComponentRegistrationBuilder::serialize_hooks].
+pub trait SerializeHooks {
+    /// Called on a copy of the component right before it is serialized.
+    fn pre_serialize(&mut self) {}
+    /// Called after the component has been built from serialized properties.
+    fn post_deserialize(&mut self) {}
+}
+
+#[derive(Default)]
+pub struct ResourceRegistry {
+    pub registrations: HashMap<TypeId, ResourceRegistration>,
+    pub names: TypeNameIndex,
+}
+
+impl ResourceRegistry {
+    pub fn register<T>(&mut self)
+    where
+        T: Properties + Resource + FromResources,
+    {
+        self.add_registration(ResourceRegistration::of::<T>());
+    }
+
+    pub fn add_registration(&mut self, registration: ResourceRegistration) {
+        self.names.insert(
+            &registration.short_name,
+            registration.long_name,
+            registration.ty,
+        );
+        self.registrations.insert(registration.ty, registration);
+    }
+
+    pub fn get(&self, type_id: &TypeId) -> Option<&ResourceRegistration> {
+        self.registrations.get(type_id)
+    }
+
+    pub fn get_with_full_name(&self, full_name: &str) -> Option<&ResourceRegistration> {
+        self.names
+            .get_with_full_name(full_name)
+            .and_then(|id| self.registrations.get(&id))
+    }
+
+    pub fn get_with_short_name(&self, short_name: &str) -> Option<&ResourceRegistration> {
+        self.names
+            .get_with_short_name(short_name)
+            .and_then(|id| self.registrations.get(&id))
+    }
+
+    /// Returns the resource with the given short or full name. Ambiguous short names return
+    /// `None`; check them with [ResourceRegistry::is_ambiguous].
+    pub fn get_with_name(&self, type_name: &str) -> Option<&ResourceRegistration> {
+        self.names
+            .get_with_name(type_name)
+            .and_then(|id| self.registrations.get(&id))
+    }
+
+    pub fn is_ambiguous(&self, short_name: &str) -> bool {
+        self.names.is_ambiguous(short_name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ResourceRegistration> {
+        self.registrations.values()
+    }
+}
+
+#[derive(Clone)]
+pub struct ResourceRegistration {
+    pub ty: TypeId,
+    pub short_name: String,
+    pub long_name: &'static str,
+    resource_insert_fn: fn(&mut Resources, &dyn Property),
+    resource_properties_fn: fn(&Resources) -> Option<DynamicProperties>,
+}
+
+impl ResourceRegistration {
+    pub fn of<T: Properties + Resource + FromResources>() -> Self {
+        Self {
+            ty: TypeId::of::<T>(),
+            resource_insert_fn: |resources: &mut Resources, property: &dyn Property| {
+                let mut resource = T::from_resources(resources);
+                resource.apply(property);
+                resources.insert(resource);
+            },
+            resource_properties_fn: |resources: &Resources| {
+                resources.get::<T>().map(|resource| resource.to_dynamic())
+            },
+            short_name: PropertyTypeRegistration::get_short_name(std::any::type_name::<T>()),
+            long_name: std::any::type_name::<T>(),
+        }
+    }
+
+    /// Inserts the resource described by `property`, replacing any existing instance.
+    pub fn insert_property_into_resources(
+        &self,
+        resources: &mut Resources,
+        property: &dyn Property,
+    ) {
+        (self.resource_insert_fn)(resources, property);
+    }
+
+    /// Returns a dynamic copy of the resource, if it exists.
+    pub fn get_resource_properties(&self, resources: &Resources) -> Option<DynamicProperties> {
+        (self.resource_properties_fn)(resources)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ComponentRegistration, ComponentRegistry, ResourceRegistry, SerializeHooks, BLOB_FIELD,
+    };
+    use bevy_ecs::{Entity, Resources, World};
+    use bevy_property::{
+        DynamicProperties, Properties, PropertiesVal, Property, PropertyTypeRegistry,
+    };
+    use serde::{Deserialize, Serialize};
+    use std::any::TypeId;
+
+    #[derive(Properties, Default)]
+    struct Health {
+        value: f32,
+    }
+
+    #[derive(Properties)]
+    struct Mana {
+        value: f32,
+    }
+
+    impl Default for Mana {
+        fn default() -> Self {
+            Mana { value: 100.0 }
+        }
+    }
+
+    #[derive(Properties, Default, Clone)]
+    struct Direction {
+        x: f32,
+        y: f32,
+    }
+
+    impl SerializeHooks for Direction {
+        fn pre_serialize(&mut self) {
+            let length = (self.x * self.x + self.y * self.y).sqrt();
+            self.x /= length;
+            self.y /= length;
+        }
+
+        fn post_deserialize(&mut self) {
+            self.x *= 2.0;
+            self.y *= 2.0;
+        }
+    }
+
+    #[derive(Properties, Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+    struct MeshData {
+        vertices: Vec<f32>,
+        indices: Vec<u32>,
+    }
+
+    #[derive(Properties, Default)]
+    struct Stamina {
+        value: f32,
+    }
+
+    fn add_clamped_health(
+        world: &mut World,
+        _resources: &Resources,
+        entity: Entity,
+        property: &dyn Property,
+    ) {
+        let mut health = Health::default();
+        health.apply(property);
+        health.value = health.value.max(0.0);
+        world.insert_one(entity, health).unwrap();
+    }
+
+    #[test]
+    fn manual_registration() {
+        let mut registry = ComponentRegistry::default();
+        registry.add_registration(ComponentRegistration {
+            ty: TypeId::of::<Health>(),
+            short_name: "Health".to_string(),
+            long_name: "game::Health",
+            component_add_fn: add_clamped_health,
+            ..ComponentRegistration::of::<Health>()
+        });
+
+        let registration = registry.get_with_name("Health").unwrap();
+        assert_eq!(registration.ty, TypeId::of::<Health>());
+        assert!(registry.get_with_full_name("game::Health").is_some());
+
+        let mut world = World::default();
+        let resources = Resources::default();
+        let entity = world.reserve_entity();
+        let mut health = DynamicProperties::map();
+        health.set("value", -5.0f32);
+        registration.add_property_to_entity(&mut world, &resources, entity, &health);
+        assert_eq!(world.get::<Health>(entity).unwrap().value, 0.0);
+    }
+
+    #[test]
+    fn export_schema() {
+        let mut registry = ComponentRegistry::default();
+        registry.register_default::<Health>();
+        registry.register_default::<Mana>();
+        registry.register::<Stamina>();
+
+        let schema = registry
+            .export_schema(&PropertyTypeRegistry::default())
+            .unwrap();
+        assert_eq!(schema.len(), 2);
+        assert!(!schema.contains_key(std::any::type_name::<Stamina>()));
+        let mana = &schema[std::any::type_name::<Mana>()];
+        assert_eq!(mana["map"]["value"], 100.0);
+    }
+
+    #[test]
+    fn serialize_hooks() {
+        let registration = ComponentRegistration::build::<Direction>()
+            .serialize_hooks()
+            .finish();
+        let mut world = World::default();
+        let entity = world.spawn((Direction { x: 3.0, y: 4.0 },));
+
+        let archetype = world
+            .archetypes()
+            .find(|archetype| archetype.has::<Direction>())
+            .unwrap();
+        let serialized = registration.get_serialized_component(archetype, 0).unwrap();
+        assert_eq!(*serialized.prop_val::<f32>("x").unwrap(), 0.6);
+        assert_eq!(*serialized.prop_val::<f32>("y").unwrap(), 0.8);
+        assert_eq!(world.get::<Direction>(entity).unwrap().x, 3.0);
+
+        let mut loaded_world = World::default();
+        let loaded_entity = loaded_world.spawn(());
+        registration.add_property_to_entity(
+            &mut loaded_world,
+            &Resources::default(),
+            loaded_entity,
+            &serialized,
+        );
+        let loaded = loaded_world.get::<Direction>(loaded_entity).unwrap();
+        assert_eq!((loaded.x, loaded.y), (1.2, 1.6));
+    }
+
+    #[test]
+    fn blob_component() {
+        let registration = ComponentRegistration::build::<MeshData>().blob().finish();
+        let mesh = MeshData {
+            vertices: vec![0.0, 1.0, 0.5],
+            indices: vec![0, 1, 2],
+        };
+        let mut world = World::default();
+        world.spawn((mesh.clone(),));
+
+        let archetype = world
+            .archetypes()
+            .find(|archetype| archetype.has::<MeshData>())
+            .unwrap();
+        let serialized = registration.get_serialized_component(archetype, 0).unwrap();
+        assert_eq!(serialized.prop_len(), 1);
+        assert!(serialized.prop_val::<String>(BLOB_FIELD).is_some());
+
+        let mut loaded_world = World::default();
+        let entity = loaded_world.spawn(());
+        registration.add_property_to_entity(
+            &mut loaded_world,
+            &Resources::default(),
+            entity,
+            &serialized,
+        );
+        assert_eq!(*loaded_world.get::<MeshData>(entity).unwrap(), mesh);
+    }
+
+    #[derive(Properties, Deserialize, Default)]
+    struct Unencodable {
+        value: f32,
+    }
+
+    impl Serialize for Unencodable {
+        fn serialize<S>(&self, _serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            Err(serde::ser::Error::custom("unencodable"))
+        }
+    }
+
+    #[test]
+    fn unencodable_blob_component() {
+        let registration = ComponentRegistration::build::<Unencodable>()
+            .blob()
+            .finish();
+        let mut world = World::default();
+        world.spawn((Unencodable { value: 1.0 },));
+        let archetype = world
+            .archetypes()
+            .find(|archetype| archetype.has::<Unencodable>())
+            .unwrap();
+        let error = registration
+            .get_serialized_component(archetype, 0)
+            .unwrap_err();
+        assert!(error.contains("unencodable"));
+    }
+
+    #[test]
+    fn corrupted_blob_component() {
+        let registration = ComponentRegistration::build::<MeshData>().blob().finish();
+        let blob = |encoded: &str| {
+            let mut properties = DynamicProperties::map();
+            properties.type_name = std::any::type_name::<MeshData>().to_string();
+            properties.set(BLOB_FIELD, encoded.to_string());
+            properties
+        };
+        let truncated = blob(&base64::encode(&[1u8]));
+        let not_base64 = blob("not base64!");
+        let missing = DynamicProperties::map();
+
+        let mut world = World::default();
+        let entity = world.spawn(());
+        for property in [truncated, not_base64, missing].iter() {
+            assert!(registration.validate_property(property).is_err());
+            registration.add_property_to_entity(
+                &mut world,
+                &Resources::default(),
+                entity,
+                property,
+            );
+            assert!(world.get::<MeshData>(entity).is_err());
+        }
+    }
+
+    mod other {
+        use bevy_property::Properties;
+
+        #[derive(Properties, Default)]
+        pub struct Health {
+            pub value: f32,
+        }
+    }
+
+    #[test]
+    fn unregister() {
+        let mut registry = ComponentRegistry::default();
+        registry.register::<Health>();
+        registry.register::<Mana>();
+        assert!(registry.is_registered::<Health>());
+
+        assert!(registry.unregister::<Health>());
+        assert!(!registry.unregister::<Health>());
+        assert!(!registry.is_registered::<Health>());
+        assert!(registry.get_with_name("Health").is_none());
+
+        assert!(registry.unregister_by_name("Mana"));
+        assert!(!registry.is_registered::<Mana>());
+        assert!(!registry.unregister_by_name("Mana"));
+
+        // removing one of two types sharing a short name makes the other reachable by it again
+        registry.register::<Health>();
+        registry.register::<other::Health>();
+        assert!(registry.get_with_short_name("Health").is_none());
+        assert!(registry.unregister::<Health>());
+        let registration = registry.get_with_name("Health").unwrap();
+        assert_eq!(registration.ty, TypeId::of::<other::Health>());
+    }
+
+    #[test]
+    fn ambiguous_resource_name() {
+        let mut registry = ResourceRegistry::default();
+        registry.register::<Health>();
+        registry.register::<other::Health>();
+
+        assert!(registry.get_with_name("Health").is_none());
+        assert!(registry.is_ambiguous("Health"));
+        let registration = registry
+            .get_with_name(std::any::type_name::<other::Health>())
+            .unwrap();
+        assert_eq!(registration.ty, TypeId::of::<other::Health>());
+    }
+
+    #[test]
+    fn describe() {
+        let mut registry = ComponentRegistry::default();
+        registry.register_default::<Mana>();
+        registry.register::<Stamina>();
+        registry.register_with_doc::<Health>("Hit points");
+
+        let report = registry.describe();
+        let section = |short_name: &str| {
+            report
+                .split("\n\n")
+                .find(|section| section.starts_with(&format!("{}\n", short_name)))
+                .unwrap()
+                .to_string()
+        };
+        let stamina = section("Stamina");
+        assert!(stamina.contains(&format!("full name: {}", std::any::type_name::<Stamina>())));
+        assert!(stamina.contains("default: no"));
+        assert!(stamina.contains("doc: no"));
+        let mana = section("Mana");
+        assert!(mana.contains("default: yes"));
+        assert!(mana.contains("resolve from resources: no"));
+        assert!(section("Health").contains("doc: yes"));
+
+        let registration = registry.get(&TypeId::of::<Stamina>()).unwrap();
+        assert!(stamina.contains(&format!("name hash: {:016x}", registration.name_hash())));
+        assert_eq!(
+            registration.name_hash(),
+            ComponentRegistration::of::<Stamina>().name_hash()
+        );
+    }
+}